@@ -0,0 +1,125 @@
+use std::fmt::Write;
+
+use bitboard;
+use board;
+use board::Board;
+use solver;
+use solver::{Outcome, Solver};
+
+// Returns a row of markers, one below each column of Board::render().
+fn marker_row(marker: impl Fn(u64) -> char) -> String {
+    let mut row = String::new();
+    for col in 0..7 {
+        row.push(' ');
+        row.push(marker(col));
+    }
+    return row;
+}
+
+// Analyzes a position for teaching purposes: it shows the
+// board and, for every column, the outcome and score for the player to move.
+pub fn analyze(solver: &mut Solver, board: Board) -> String {
+    let mut result = board.render(bitboard::empty());
+    if board.other().won() {
+        result.push_str("The game is over, the last move won.\n");
+        return result;
+    }
+    let moves = board.moves();
+    if moves.empty() {
+        result.push_str("The game is over, it's a draw.\n");
+        return result;
+    }
+
+    let non_losing = board.non_losing_moves(moves);
+    let winning = board.winning_moves();
+    let blocking = board.blocking_moves();
+    let scores = solver.score_columns(board);
+
+    let outcome_row = marker_row(|col| match scores[col as usize] {
+        None => '-',
+        Some(score) => match solver::outcome_for_score(score) {
+            Outcome::Win => 'W',
+            Outcome::Draw => 'D',
+            Outcome::Loss => 'L',
+        },
+    });
+    let non_losing_row = marker_row(|col| {
+        if non_losing.for_column(col).empty() {
+            '.'
+        } else {
+            '+'
+        }
+    });
+    let threat_row = marker_row(|col| {
+        if !winning.for_column(col).empty() {
+            'W'
+        } else if !blocking.for_column(col).empty() {
+            'B'
+        } else {
+            '.'
+        }
+    });
+    writeln!(
+        result,
+        "{}   outcome (W: win, D: draw, L: loss)",
+        outcome_row
+    )
+    .unwrap();
+    writeln!(result, "{}   non-losing moves", non_losing_row).unwrap();
+    writeln!(result, "{}   W: immediate win, B: forced block", threat_row).unwrap();
+
+    let player = if board.side_to_move() == board::FIRST_PLAYER {
+        'X'
+    } else {
+        'O'
+    };
+    writeln!(result, "{} to move.", player).unwrap();
+    for (col, score) in scores.iter().enumerate() {
+        let description = match *score {
+            None => "illegal".to_string(),
+            Some(score) => match solver::outcome_for_score(score) {
+                Outcome::Win => format!("win, score {:+}", score),
+                Outcome::Draw => "draw, score 0".to_string(),
+                Outcome::Loss => format!("loss, score {:+}", score),
+            },
+        };
+        writeln!(result, "Column {}: {}", col + 1, description).unwrap();
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cache;
+
+    #[test]
+    fn test_analyze() {
+        let mut solver = solver::new(16, cache::Format::Full);
+        // X threatens to win in both 3 and 7, O can only block one of them.
+        let board = board::from_moves("44556").unwrap();
+        let analysis = analyze(&mut solver, board);
+        assert_eq!(
+            analysis,
+            " . . . . . . .
+ . . . . . . .
+ . . . . . . .
+ . . . . . . .
+ . . . O O . .
+ . . . X X X .
+ 1 2 3 4 5 6 7
+ L L L L L L L   outcome (W: win, D: draw, L: loss)
+ . . . . . . .   non-losing moves
+ . . B . . . B   W: immediate win, B: forced block
+O to move.
+Column 1: loss, score -18
+Column 2: loss, score -18
+Column 3: loss, score -18
+Column 4: loss, score -18
+Column 5: loss, score -18
+Column 6: loss, score -18
+Column 7: loss, score -18
+"
+        );
+    }
+}
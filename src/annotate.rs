@@ -0,0 +1,114 @@
+// Annotates game records with the solver. A game record is a line of moves,
+// where each move is a column from 1 to 7 (e.g. "4453"). The annotated record
+// looks like this (analyzed from the sixth move):
+//
+//   [Game "445567433"]
+//   [Result "*"]
+//   1. 4
+//   2. 4
+//   3. 5
+//   4. 5
+//   5. 6
+//   6. 7 (-18)
+//   7. 4?? (-1) best 3 (+18)
+//   8. 3 (+1)
+//   9. 3 (-1)
+//
+// The Result tag is "1-0" if the first player won, "0-1" if the second player
+// won, "1/2-1/2" for a draw and "*" if the game is unfinished. Every move is
+// followed by its score from the point of view of the player that made it
+// (see Solver::score()), and if it isn't the best move, a mark:
+// - "??" for a blunder: a move that changes the outcome (win, draw or loss).
+// - "?" for an inaccuracy: a move with the same outcome as the best move, but
+//   a lower score (a slower win, or a faster loss).
+// and the best alternative with its score. Moves before `first_ply` are not
+// analyzed, since solving early positions takes a long time; those are
+// listed without a score.
+use std::fmt::Write;
+
+use board;
+use board::GameResult;
+use solver;
+use solver::Solver;
+
+fn format_score(score: i64) -> String {
+    if score == 0 {
+        return "0".to_string();
+    }
+    return format!("{:+}", score);
+}
+
+// Returns the annotated record for a game, or None if it contains an invalid
+// move or moves after the game was won.
+pub fn annotate(solver: &mut Solver, moves: &str, first_ply: u64) -> Option<String> {
+    let mut result = String::new();
+    let mut board = board::empty();
+    let mut body = String::new();
+    for (ply, c) in moves.bytes().enumerate() {
+        let ply = ply as u64;
+        if !(b'1'..=b'7').contains(&c) || board.other().won() {
+            return None;
+        }
+        let col = (c - b'1') as u64;
+        let move_ = board.moves().for_column(col);
+        if move_.empty() {
+            return None;
+        }
+        write!(body, "{}. {}", ply + 1, col + 1).unwrap();
+        if ply >= first_ply {
+            let scores = solver.score_columns(board);
+            let score = scores[col as usize].unwrap();
+            let best_col = solver::best_column(scores).unwrap();
+            let best = scores[best_col as usize].unwrap();
+            if solver::outcome_for_score(score) < solver::outcome_for_score(best) {
+                body.push_str("??");
+            } else if score < best {
+                body.push('?');
+            }
+            write!(body, " ({})", format_score(score)).unwrap();
+            if score < best {
+                write!(body, " best {} ({})", best_col + 1, format_score(best)).unwrap();
+            }
+        }
+        body.push('\n');
+        board = board.do_move(move_);
+    }
+
+    let game_result = match board.outcome() {
+        Some(GameResult::FirstPlayerWins) => "1-0",
+        Some(GameResult::SecondPlayerWins) => "0-1",
+        Some(GameResult::Draw) => "1/2-1/2",
+        None => "*",
+    };
+    writeln!(result, "[Game \"{}\"]", moves).unwrap();
+    writeln!(result, "[Result \"{}\"]", game_result).unwrap();
+    result.push_str(&body);
+    return Some(result);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cache;
+
+    #[test]
+    fn test_annotate() {
+        let mut solver = solver::new(16, cache::Format::Full);
+        // Solving early positions takes long, so only the last moves of this
+        // game are analyzed.
+        let record = annotate(&mut solver, "62352673232646611533624214375411", 26).unwrap();
+        assert!(record.starts_with(
+            "[Game \"62352673232646611533624214375411\"]\n[Result \"*\"]\n1. 6\n2. 2\n"
+        ));
+        assert!(record.ends_with(
+            "26. 4\n27. 3 (-8)\n28. 7? (+7) best 5 (+8)\n29. 5 (-7)\n30. 4?? (-6) best 5 (+7)\n31. 1?? (-6) best 4 (+6)\n32. 1?? (-5) best 5 (+6)\n"
+        ));
+        assert_eq!(annotate(&mut solver, "44x", 3), None);
+        assert_eq!(
+            annotate(&mut solver, "4455667", 7).unwrap(),
+            "[Game \"4455667\"]\n[Result \"1-0\"]\n1. 4\n2. 4\n3. 5\n4. 5\n5. 6\n6. 6\n7. 7\n"
+        );
+        // Moves after the game was won.
+        assert_eq!(annotate(&mut solver, "44556677", 8), None);
+    }
+}
@@ -1,6 +1,9 @@
-extern crate test;
-
+use std::fmt;
+#[cfg(feature = "nightly")]
 use std::simd::u64x4;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use render;
 
 // BitBoard represents the stones of a single player with bits.
 // Layout:
@@ -37,7 +40,74 @@ const ROW4: u64 = ROW3 << VER_STRIDE;
 const ROW5: u64 = ROW4 << VER_STRIDE;
 
 const VALID_PLACES: u64 = COL0 * ROW0;
+// Rows 0, 2 and 4: the first player gets these places when the second player
+// follows up (always plays in the same column as the first player).
+const EVEN_ROWS: u64 = ROW0 | ROW2 | ROW4;
+const ODD_ROWS: u64 = ROW1 | ROW3 | ROW5;
+
+// The implementations of the bitboard kernels: won(), wins() and almost_wins().
+// By default, the fastest one that the CPU supports is used.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Kernels {
+    // The *_no_simd() implementations.
+    Scalar,
+    // The *_simd() implementations where they are faster, only with the "nightly"
+    // feature. They use the CPU features the binary was compiled for.
+    Simd,
+    // Like Simd (or Scalar without the "nightly" feature), but compiled for
+    // CPUs with AVX2, BMI2 and POPCNT. Only on x86_64.
+    Avx2,
+}
+
+const ALL_KERNELS: [Kernels; 3] = [Kernels::Scalar, Kernels::Simd, Kernels::Avx2];
+
+// The index of the selected kernels in ALL_KERNELS plus one, or 0 before the
+// CPU features are detected.
+static KERNELS: AtomicU8 = AtomicU8::new(0);
+
+// Returns whether the kernels can be used with this binary and CPU.
+pub fn supported(kernels: Kernels) -> bool {
+    return match kernels {
+        Kernels::Scalar => true,
+        Kernels::Simd => cfg!(feature = "nightly"),
+        #[cfg(target_arch = "x86_64")]
+        Kernels::Avx2 => {
+            is_x86_feature_detected!("avx2")
+                && is_x86_feature_detected!("bmi2")
+                && is_x86_feature_detected!("popcnt")
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        Kernels::Avx2 => false,
+    };
+}
 
+// Returns the kernels in use.
+pub fn kernels() -> Kernels {
+    let index = KERNELS.load(Ordering::Relaxed);
+    if index != 0 {
+        return ALL_KERNELS[index as usize - 1];
+    }
+    // The last supported kernels are the fastest.
+    let kernels = *ALL_KERNELS
+        .iter()
+        .rfind(|kernels| supported(**kernels))
+        .unwrap();
+    force_kernels(kernels);
+    return kernels;
+}
+
+// Selects the kernels to use from now on, for testing or benchmarking. Returns
+// false if they aren't supported.
+pub fn force_kernels(kernels: Kernels) -> bool {
+    if !supported(kernels) {
+        return false;
+    }
+    let index = ALL_KERNELS.iter().position(|k| *k == kernels).unwrap();
+    KERNELS.store(index as u8 + 1, Ordering::Relaxed);
+    return true;
+}
+
+#[cfg(feature = "nightly")]
 fn stridex4() -> u64x4 {
     return u64x4::from([HOR_STRIDE, VER_STRIDE, DIAG_DOWN_STRIDE, DIAG_UP_STRIDE]);
 }
@@ -47,6 +117,7 @@ fn and4r(x: u64, stride: u64) -> u64 {
     return and2 & (and2 >> (2 * stride));
 }
 
+#[cfg(feature = "nightly")]
 fn and4rx4(x: u64x4, stride: u64x4) -> u64x4 {
     let and2 = x & (x >> stride);
     return and2 & (and2 >> (u64x4::splat(2) * stride));
@@ -62,6 +133,7 @@ fn or4r(x: u64, stride: u64) -> u64 {
     return or2 | (or2 >> (2 * stride));
 }
 
+#[cfg(feature = "nightly")]
 fn or4lx4(x: u64x4, stride: u64x4) -> u64x4 {
     let or2 = x | (x << stride);
     return or2 | (or2 << (u64x4::splat(2) * stride));
@@ -77,6 +149,7 @@ fn comb34(x: u64, stride: u64) -> u64 {
     return b21 | b12;
 }
 
+#[cfg(feature = "nightly")]
 fn comb34x4(x: u64x4, stride: u64x4) -> u64x4 {
     let and2 = x & (x >> stride);
     let xor2 = x ^ (x >> stride);
@@ -94,6 +167,32 @@ pub fn random(seed: u64) -> BitBoard {
     return BitBoard { board: seed };
 }
 
+// The inverse of BitBoard::key(): returns our stones and the other stones, or
+// None if a column has no marker bit or bits above the 49 bits are set.
+pub fn from_key(key: u64) -> Option<(BitBoard, BitBoard)> {
+    if key >> 49 != 0 {
+        return None;
+    }
+    let mut ours = 0;
+    let mut placed = 0;
+    for col in 0..7 {
+        let column = (key >> (col * 7)) & 0x7f;
+        if column == 0 {
+            return None;
+        }
+        // The stones are below the highest bit, which is the marker.
+        let stones = (1 << (63 - column.leading_zeros())) - 1;
+        ours |= (column & stones) << (col * HOR_STRIDE);
+        placed |= stones << (col * HOR_STRIDE);
+    }
+    return Some((
+        BitBoard { board: ours },
+        BitBoard {
+            board: placed & !ours,
+        },
+    ));
+}
+
 impl BitBoard {
     pub fn raw(self) -> u64 {
         return self.board;
@@ -112,9 +211,32 @@ impl BitBoard {
         return self.board & 1 << (col * HOR_STRIDE + row * VER_STRIDE) != 0;
     }
 
+    // Returns true if no bits are set outside of the 7x6 places, like the
+    // separator bits above every column.
+    pub fn in_bounds(self) -> bool {
+        return self.out_of_bounds().empty();
+    }
+
+    // Returns the bits that are set outside of the 7x6 places.
+    pub fn out_of_bounds(self) -> BitBoard {
+        return BitBoard {
+            board: self.board & !VALID_PLACES,
+        };
+    }
+
+    // Given the other player's stones, returns true if all stones are in
+    // valid places, stacked from the bottom of their column, and no place has
+    // stones of both players.
+    pub fn stacked(self, other: BitBoard) -> bool {
+        let placed = self.board | other.board;
+        return self.board & other.board == 0
+            && placed & !VALID_PLACES == 0
+            && placed & (placed + ROW0) == 0;
+    }
+
     // Returns true if more than 1 bit is set.
     pub fn more_than_1(self) -> bool {
-        return self.board & (self.board - 1) != 0;
+        return self.board & self.board.wrapping_sub(1) != 0;
     }
 
     // Moves returns a bitboard where position i is 1 if a stone can be placed there.
@@ -180,14 +302,41 @@ impl BitBoard {
         return h4 | v4 | dd4 | du4 != 0;
     }
 
+    // Only used by the tests and benchmarks, see won_default().
+    #[cfg(feature = "nightly")]
+    #[allow(dead_code)]
     fn won_simd(self) -> bool {
         return and4rx4(u64x4::splat(self.board), stridex4()) != u64x4::splat(0);
     }
 
-    pub fn won(self) -> bool {
+    // Measured to be faster than won_simd().
+    fn won_default(self) -> bool {
         return self.won_no_simd();
     }
 
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,bmi2,popcnt")]
+    unsafe fn won_avx2(self) -> bool {
+        return self.won_default();
+    }
+
+    pub fn won(self) -> bool {
+        return self.won_with(kernels());
+    }
+
+    // won() with the given kernels, which must be supported.
+    fn won_with(self, kernels: Kernels) -> bool {
+        return match kernels {
+            Kernels::Scalar => self.won_no_simd(),
+            Kernels::Simd => self.won_default(),
+            // Safe because the CPU supports the features, see supported().
+            #[cfg(target_arch = "x86_64")]
+            Kernels::Avx2 => unsafe { self.won_avx2() },
+            #[cfg(not(target_arch = "x86_64"))]
+            Kernels::Avx2 => unreachable!(),
+        };
+    }
+
     fn wins_no_simd(self) -> BitBoard {
         let ph = or4l(and4r(self.board, HOR_STRIDE), HOR_STRIDE);
         let pv = or4l(and4r(self.board, VER_STRIDE), VER_STRIDE);
@@ -198,6 +347,7 @@ impl BitBoard {
         };
     }
 
+    #[cfg(feature = "nightly")]
     fn wins_simd(self) -> BitBoard {
         let result = or4lx4(and4rx4(u64x4::splat(self.board), stridex4()), stridex4());
         return BitBoard {
@@ -205,8 +355,34 @@ impl BitBoard {
         };
     }
 
-    pub fn wins(self) -> BitBoard {
+    fn wins_default(self) -> BitBoard {
+        #[cfg(feature = "nightly")]
         return self.wins_simd();
+        #[cfg(not(feature = "nightly"))]
+        return self.wins_no_simd();
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,bmi2,popcnt")]
+    unsafe fn wins_avx2(self) -> BitBoard {
+        return self.wins_default();
+    }
+
+    pub fn wins(self) -> BitBoard {
+        return self.wins_with(kernels());
+    }
+
+    // wins() with the given kernels, which must be supported.
+    fn wins_with(self, kernels: Kernels) -> BitBoard {
+        return match kernels {
+            Kernels::Scalar => self.wins_no_simd(),
+            Kernels::Simd => self.wins_default(),
+            // Safe because the CPU supports the features, see supported().
+            #[cfg(target_arch = "x86_64")]
+            Kernels::Avx2 => unsafe { self.wins_avx2() },
+            #[cfg(not(target_arch = "x86_64"))]
+            Kernels::Avx2 => unreachable!(),
+        };
     }
 
     // Note: almost_wins might leak bits out of the bitboard.
@@ -221,6 +397,7 @@ impl BitBoard {
     }
 
     // Like almost_wins(), but with a SIMD implementation.
+    #[cfg(feature = "nightly")]
     fn almost_wins_simd(self) -> BitBoard {
         let result = or4lx4(comb34x4(u64x4::splat(self.board), stridex4()), stridex4());
         return BitBoard {
@@ -228,8 +405,34 @@ impl BitBoard {
         };
     }
 
-    pub fn almost_wins(self) -> BitBoard {
+    fn almost_wins_default(self) -> BitBoard {
+        #[cfg(feature = "nightly")]
         return self.almost_wins_simd();
+        #[cfg(not(feature = "nightly"))]
+        return self.almost_wins_no_simd();
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2,bmi2,popcnt")]
+    unsafe fn almost_wins_avx2(self) -> BitBoard {
+        return self.almost_wins_default();
+    }
+
+    pub fn almost_wins(self) -> BitBoard {
+        return self.almost_wins_with(kernels());
+    }
+
+    // almost_wins() with the given kernels, which must be supported.
+    fn almost_wins_with(self, kernels: Kernels) -> BitBoard {
+        return match kernels {
+            Kernels::Scalar => self.almost_wins_no_simd(),
+            Kernels::Simd => self.almost_wins_default(),
+            // Safe because the CPU supports the features, see supported().
+            #[cfg(target_arch = "x86_64")]
+            Kernels::Avx2 => unsafe { self.almost_wins_avx2() },
+            #[cfg(not(target_arch = "x86_64"))]
+            Kernels::Avx2 => unreachable!(),
+        };
     }
 
     // Given a bitboard of the opponent, computes the number of possible four-in-a-rows
@@ -245,6 +448,46 @@ impl BitBoard {
         return k;
     }
 
+    // Given a bitboard of the opponent, returns true if every column contains
+    // an even number of stones.
+    pub fn even_heights(self, other: BitBoard) -> bool {
+        return self.moves(other).board & ODD_ROWS == 0;
+    }
+
+    // Given a bitboard of the opponent, returns our stones plus the empty places
+    // on even rows. These are the places we end up with as the first player if
+    // every column has an even height and the opponent follows up.
+    pub fn claim_even_rows(self, other: BitBoard) -> BitBoard {
+        return BitBoard {
+            board: self.board | (!(self.board | other.board) & EVEN_ROWS),
+        };
+    }
+
+    // Like claim_even_rows(), but for the second player, who gets the odd rows.
+    pub fn claim_odd_rows(self, other: BitBoard) -> BitBoard {
+        return BitBoard {
+            board: self.board | (!(self.board | other.board) & ODD_ROWS),
+        };
+    }
+
+    // Given a bitboard of the opponent, returns the empty places where we would
+    // complete a four-in-a-row (threats).
+    pub fn threats(self, other: BitBoard) -> BitBoard {
+        return BitBoard {
+            board: self.almost_wins().board & !(self.board | other.board) & VALID_PLACES,
+        };
+    }
+
+    pub fn intersect(self, other: BitBoard) -> BitBoard {
+        return BitBoard {
+            board: self.board & other.board,
+        };
+    }
+
+    pub fn count(self) -> u32 {
+        return self.board.count_ones();
+    }
+
     // Returns true if we can make a winning move (any column).
     // Requires the result of Board::moves() to compute.
     pub fn can_win(self, moves: BitBoard) -> bool {
@@ -258,6 +501,18 @@ impl BitBoard {
         return self;
     }
 
+    // Given the bitboard of the other player, returns a 49-bit key that is
+    // unique for the position. Per column it holds our stones plus a marker bit
+    // right above the highest stone, which takes 7 bits per column.
+    pub fn key(self, other: BitBoard) -> u64 {
+        let marked = self.board | ((self.board | other.board) + ROW0);
+        let mut key = 0;
+        for col in 0..7 {
+            key |= ((marked >> (col * HOR_STRIDE)) & 0x7f) << (col * 7);
+        }
+        return key;
+    }
+
     pub fn do_move(self, move_: BitBoard) -> BitBoard {
         return BitBoard {
             board: self.board | move_.board,
@@ -281,62 +536,288 @@ impl BitBoard {
     }
 }
 
+// The set bits as stones of the first player, see render::render_bitboard().
+impl fmt::Display for BitBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return f.write_str(&render::render_bitboard(
+            *self,
+            render::Style::Ascii,
+            empty(),
+        ));
+    }
+}
+
+impl fmt::Debug for BitBoard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "BitBoard({:#x})\n{}", self.board, self);
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
-    const ITERATION_COUNT: u64 = 100;
-
-    #[bench]
-    fn bench_won_no_simd(b: &mut test::Bencher) {
-        b.iter(|| {
-            for i in 0..ITERATION_COUNT {
-                // `i` is not a valid board, but it shouldn't matter for benchmarking.
-                test::black_box(BitBoard { board: i }.won_no_simd());
+    use verify;
+
+    #[test]
+    fn test_kernels() {
+        let detected = kernels();
+        assert!(supported(detected));
+        assert!(supported(Kernels::Scalar));
+        // The kernels are passed explicitly instead of being forced, which would
+        // affect the tests that run in parallel.
+        for kernels in ALL_KERNELS {
+            if !supported(kernels) {
+                continue;
             }
-        });
+            for i in 0..1000u64 {
+                let board = random(i.wrapping_mul(0x9e3779b97f4a7c15) & VALID_PLACES);
+                assert_eq!(board.won_with(kernels), board.won_no_simd());
+                assert_eq!(board.wins_with(kernels).raw(), board.wins_no_simd().raw());
+                assert_eq!(
+                    board.almost_wins_with(kernels).raw(),
+                    board.almost_wins_no_simd().raw()
+                );
+            }
+        }
     }
 
-    #[bench]
-    fn bench_won_simd(b: &mut test::Bencher) {
-        b.iter(|| {
-            for i in 0..ITERATION_COUNT {
-                test::black_box(BitBoard { board: i }.won_simd());
+    // Returns positions of random games as (stones of the player to move, stones
+    // of the other player), including the final positions where the last move won.
+    fn random_positions(games: usize, mut seed: u64) -> Vec<(BitBoard, BitBoard)> {
+        let mut positions = Vec::new();
+        for _ in 0..games {
+            for board in verify::random_game(&mut seed) {
+                positions.push((board.current(), board.other()));
             }
-        });
+        }
+        return positions;
+    }
+
+    fn cell(col: u64, row: u64) -> u64 {
+        return empty().flip(col, row).raw();
     }
 
-    #[bench]
-    fn bench_wins_no_simd(b: &mut test::Bencher) {
-        b.iter(|| {
-            for i in 0..ITERATION_COUNT {
-                test::black_box(BitBoard { board: i }.wins_no_simd());
+    // Returns all possible four-in-a-rows as lists of (column, row).
+    fn lines() -> Vec<[(u64, u64); 4]> {
+        let mut lines = Vec::new();
+        for col in 0..7 {
+            for row in 0..6 {
+                for (col_step, row_step) in [(1, 0), (0, 1), (1, 1), (1, -1)] {
+                    let end_col = col + 3 * col_step;
+                    let end_row = row + 3 * row_step;
+                    if end_col >= 7 || !(0..6).contains(&end_row) {
+                        continue;
+                    }
+                    let mut line = [(0, 0); 4];
+                    for (i, place) in line.iter_mut().enumerate() {
+                        let i = i as i64;
+                        *place = ((col + i * col_step) as u64, (row + i * row_step) as u64);
+                    }
+                    lines.push(line);
+                }
             }
-        });
+        }
+        return lines;
     }
 
-    #[bench]
-    fn bench_wins_simd(b: &mut test::Bencher) {
-        b.iter(|| {
-            for i in 0..ITERATION_COUNT {
-                test::black_box(BitBoard { board: i }.wins_simd());
+    // Returns the places of all four-in-a-rows of the bitboard, like wins().
+    fn naive_wins(board: BitBoard) -> u64 {
+        let mut result = 0;
+        for line in lines() {
+            if line.iter().all(|(col, row)| board.is_set(*col, *row)) {
+                for (col, row) in line {
+                    result |= cell(col, row);
+                }
             }
-        });
+        }
+        return result;
     }
 
-    #[bench]
-    fn bench_almost_wins_no_simd(b: &mut test::Bencher) {
-        b.iter(|| {
-            for i in 0..ITERATION_COUNT {
-                test::black_box(BitBoard { board: i }.almost_wins_no_simd());
+    // Returns the places that would complete a four-in-a-row, whether they are
+    // empty or not.
+    fn naive_completing_places(board: BitBoard) -> u64 {
+        let mut result = 0;
+        for line in lines() {
+            let missing: Vec<_> = line
+                .iter()
+                .filter(|(col, row)| !board.is_set(*col, *row))
+                .collect();
+            if missing.len() == 1 {
+                result |= cell(missing[0].0, missing[0].1);
             }
-        });
+        }
+        return result;
     }
 
-    #[bench]
-    fn bench_almost_wins_simd(b: &mut test::Bencher) {
-        b.iter(|| {
-            for i in 0..ITERATION_COUNT {
-                test::black_box(BitBoard { board: i }.almost_wins_simd());
+    // Returns the moves after which the opponent can't complete a four-in-a-row
+    // with their next move, like non_losing_moves().
+    fn naive_non_losing_moves(current: BitBoard, other: BitBoard) -> u64 {
+        let mut result = 0;
+        for col in 0..7 {
+            let move_ = current.moves(other).for_column(col);
+            if move_.empty() {
+                continue;
+            }
+            let current = current.do_move(move_);
+            let replies = other.moves(current);
+            let loses = (0..7).any(|reply_col| {
+                let reply = replies.for_column(reply_col);
+                return !reply.empty() && naive_wins(other.do_move(reply)) != 0;
+            });
+            if !loses {
+                result |= move_.raw();
             }
-        });
+        }
+        return result;
+    }
+
+    // Returns the stones of `board` plus the stones of `other` that are not part
+    // of any four-in-a-row without stones of `board`, like add_color_less().
+    fn naive_add_color_less(board: BitBoard, other: BitBoard) -> u64 {
+        let mut result = board.raw();
+        for col in 0..7 {
+            for row in 0..6 {
+                if !other.is_set(col, row) {
+                    continue;
+                }
+                let can_be_part = lines().iter().any(|line| {
+                    return line.contains(&(col, row))
+                        && line.iter().all(|(c, r)| !board.is_set(*c, *r));
+                });
+                if !can_be_part {
+                    result |= cell(col, row);
+                }
+            }
+        }
+        return result;
+    }
+
+    #[test]
+    fn test_kernels_on_positions() {
+        for (current, other) in random_positions(500, 1) {
+            for board in [current, other] {
+                let wins = naive_wins(board);
+                // almost_wins() also contains the other places of the four-in-a-row,
+                // and places outside of the board.
+                let completing = naive_completing_places(board);
+                let almost_wins =
+                    |almost_wins: BitBoard| almost_wins.raw() & !board.raw() & VALID_PLACES;
+                assert_eq!(board.won_no_simd(), wins != 0);
+                assert_eq!(board.wins_no_simd().raw(), wins);
+                assert_eq!(almost_wins(board.almost_wins_no_simd()), completing);
+                #[cfg(feature = "nightly")]
+                {
+                    assert_eq!(board.won_simd(), wins != 0);
+                    assert_eq!(board.wins_simd().raw(), wins);
+                    assert_eq!(
+                        board.almost_wins_simd().raw(),
+                        board.almost_wins_no_simd().raw()
+                    );
+                }
+                for kernels in ALL_KERNELS {
+                    if !supported(kernels) {
+                        continue;
+                    }
+                    assert_eq!(board.won_with(kernels), wins != 0);
+                    assert_eq!(board.wins_with(kernels).raw(), wins);
+                    assert_eq!(
+                        board.almost_wins_with(kernels).raw(),
+                        board.almost_wins_no_simd().raw()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_losing_moves() {
+        for (current, other) in random_positions(500, 2) {
+            if other.won_no_simd() {
+                continue;
+            }
+            let moves = current.moves(other);
+            assert_eq!(
+                other.non_losing_moves(moves).raw(),
+                naive_non_losing_moves(current, other)
+            );
+        }
+    }
+
+    #[test]
+    fn test_add_color_less() {
+        for (current, other) in random_positions(500, 3) {
+            assert_eq!(
+                current.add_color_less(other).raw(),
+                naive_add_color_less(current, other)
+            );
+            assert_eq!(
+                other.add_color_less(current).raw(),
+                naive_add_color_less(other, current)
+            );
+        }
+    }
+
+    // Benchmarks need nightly Rust.
+    #[cfg(feature = "nightly")]
+    mod benches {
+        extern crate test;
+
+        use super::*;
+        const ITERATION_COUNT: u64 = 100;
+
+        #[bench]
+        fn bench_won_no_simd(b: &mut test::Bencher) {
+            b.iter(|| {
+                for i in 0..ITERATION_COUNT {
+                    // `i` is not a valid board, but it shouldn't matter for benchmarking.
+                    test::black_box(BitBoard { board: i }.won_no_simd());
+                }
+            });
+        }
+
+        #[bench]
+        fn bench_won_simd(b: &mut test::Bencher) {
+            b.iter(|| {
+                for i in 0..ITERATION_COUNT {
+                    test::black_box(BitBoard { board: i }.won_simd());
+                }
+            });
+        }
+
+        #[bench]
+        fn bench_wins_no_simd(b: &mut test::Bencher) {
+            b.iter(|| {
+                for i in 0..ITERATION_COUNT {
+                    test::black_box(BitBoard { board: i }.wins_no_simd());
+                }
+            });
+        }
+
+        #[bench]
+        fn bench_wins_simd(b: &mut test::Bencher) {
+            b.iter(|| {
+                for i in 0..ITERATION_COUNT {
+                    test::black_box(BitBoard { board: i }.wins_simd());
+                }
+            });
+        }
+
+        #[bench]
+        fn bench_almost_wins_no_simd(b: &mut test::Bencher) {
+            b.iter(|| {
+                for i in 0..ITERATION_COUNT {
+                    test::black_box(BitBoard { board: i }.almost_wins_no_simd());
+                }
+            });
+        }
+
+        #[bench]
+        fn bench_almost_wins_simd(b: &mut test::Bencher) {
+            b.iter(|| {
+                for i in 0..ITERATION_COUNT {
+                    test::black_box(BitBoard { board: i }.almost_wins_simd());
+                }
+            });
+        }
     }
 }
@@ -1,11 +1,47 @@
-extern crate test;
+use std::collections::HashSet;
+use std::fmt;
 
 use bitboard;
 use bitboard::BitBoard;
+use cache;
+use render;
 
 pub const FIRST_PLAYER: u64 = 0;
 pub const SECOND_PLAYER: u64 = 1;
 
+// The result of a game that is over, see Board::outcome().
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    FirstPlayerWins,
+    SecondPlayerWins,
+    Draw,
+}
+
+// The reasons why a column can't be played, see Board::play().
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    // The column isn't from 0 to 6.
+    InvalidColumn,
+    GameOver,
+    ColumnFull,
+}
+
+// The reasons why a pair of bitboards isn't a position, see Board::validate().
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BoardError {
+    // A place has stones of both players.
+    Overlap,
+    // A stone is outside the 7x6 places, e.g. in the separator bits.
+    OutOfBounds,
+    // A stone is above an empty place.
+    Floating,
+    // The player to move doesn't have as many stones as the other player, or
+    // one less.
+    StoneCount,
+    // A four-in-a-row was completed before the last move.
+    EarlyFour,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Board {
     // Stones for the player that will make the next move (this can be player 1 or 2).
@@ -21,11 +57,179 @@ pub fn empty() -> Board {
     };
 }
 
+// Returns the board after the given moves, where each character is a column
+// from 1 to 7 (e.g. "4453"). Returns None if a move is invalid or the game was
+// already won before the last move.
+pub fn from_moves(moves: &str) -> Option<Board> {
+    let mut board = empty();
+    for col in parse_moves(moves)? {
+        board = board.play(col).ok()?;
+    }
+    return Some(board);
+}
+
+// Returns the columns (0 to 6) of a move string like from_moves() takes, or
+// None if a character isn't a column from 1 to 7. The moves aren't played, so
+// they may be invalid.
+pub fn parse_moves(moves: &str) -> Option<Vec<u64>> {
+    let mut columns = Vec::new();
+    for c in moves.bytes() {
+        if !(b'1'..=b'7').contains(&c) {
+            return None;
+        }
+        columns.push((c - b'1') as u64);
+    }
+    return Some(columns);
+}
+
+// The inverse of parse_moves().
+pub fn format_moves(columns: &[u64]) -> String {
+    return columns
+        .iter()
+        .map(|col| (b'1' + *col as u8) as char)
+        .collect();
+}
+
+// The moves played from a starting position, so they can be taken back.
+pub struct MoveStack {
+    // boards[i] is the position after i moves.
+    boards: Vec<Board>,
+    columns: Vec<u64>,
+}
+
+pub fn move_stack(start: Board) -> MoveStack {
+    return MoveStack {
+        boards: vec![start],
+        columns: Vec::new(),
+    };
+}
+
+impl MoveStack {
+    // Returns the current position.
+    pub fn board(&self) -> Board {
+        return *self.boards.last().unwrap();
+    }
+
+    pub fn start(&self) -> Board {
+        return self.boards[0];
+    }
+
+    // Returns the columns played since the starting position.
+    pub fn columns(&self) -> &[u64] {
+        return &self.columns;
+    }
+
+    // Returns the stone placed by the last move, or an empty bitboard if no
+    // moves were played.
+    pub fn last_move(&self) -> BitBoard {
+        if self.columns.is_empty() {
+            return bitboard::empty();
+        }
+        let before = self.boards[self.boards.len() - 2];
+        return before.moves().for_column(*self.columns.last().unwrap());
+    }
+
+    pub fn play(&mut self, col: u64) -> Result<(), MoveError> {
+        let board = self.board().play(col)?;
+        self.boards.push(board);
+        self.columns.push(col);
+        return Ok(());
+    }
+
+    // Takes back the last move and returns its column, or returns None if no
+    // moves were played.
+    pub fn undo(&mut self) -> Option<u64> {
+        let col = self.columns.pop()?;
+        self.boards.pop();
+        return Some(col);
+    }
+}
+
+// Returns the position for a number from Board::encode(), or None if it isn't
+// an encoding or the numbers of stones don't match.
+pub fn decode(encoded: u64) -> Option<Board> {
+    let (current, other) = bitboard::from_key(encoded)?;
+    // The player to move has as many stones as the other player, or one less.
+    if !(0..=1).contains(&(other.count() as i64 - current.count() as i64)) {
+        return None;
+    }
+    return Some(Board { current, other });
+}
+
+// Returns the position for a number from Board::to_base3(), or None if it
+// isn't one: a digit above 42 digits, a stone above an empty place, or
+// numbers of stones that don't match.
+pub fn from_base3(mut number: u128) -> Option<Board> {
+    let mut first = bitboard::empty();
+    let mut second = bitboard::empty();
+    for col in 0..7 {
+        let mut empty_below = false;
+        for row in 0..6 {
+            let digit = number % 3;
+            number /= 3;
+            match digit {
+                0 => empty_below = true,
+                _ if empty_below => return None,
+                1 => first = first.flip(col, row),
+                _ => second = second.flip(col, row),
+            }
+        }
+    }
+    if number != 0 {
+        return None;
+    }
+    return match first.count() as i64 - second.count() as i64 {
+        0 => Some(Board {
+            current: first,
+            other: second,
+        }),
+        1 => Some(Board {
+            current: second,
+            other: first,
+        }),
+        _ => None,
+    };
+}
+
 impl Board {
     pub fn moves(self) -> BitBoard {
         return self.current.moves(self.other);
     }
 
+    // Plays a column (0 to 6).
+    pub fn play(self, col: u64) -> Result<Board, MoveError> {
+        if col >= 7 {
+            return Err(MoveError::InvalidColumn);
+        }
+        if self.outcome().is_some() {
+            return Err(MoveError::GameOver);
+        }
+        let move_ = self.moves().for_column(col);
+        if move_.empty() {
+            return Err(MoveError::ColumnFull);
+        }
+        return Ok(self.do_move(move_));
+    }
+
+    // Returns the columns that can be played, none if the game is over.
+    pub fn legal_columns(self) -> impl Iterator<Item = u64> {
+        let moves = if self.other.won() {
+            bitboard::empty()
+        } else {
+            self.moves()
+        };
+        return (0..7).filter(move |col| !moves.for_column(*col).empty());
+    }
+
+    // Returns the number of stones in a column, or None if the column isn't
+    // from 0 to 6.
+    pub fn column_height(self, col: u64) -> Option<u64> {
+        if col >= 7 {
+            return None;
+        }
+        return Some(self.column_top(col).map_or(0, |row| row + 1));
+    }
+
     pub fn do_move(self, move_: BitBoard) -> Board {
         return Board {
             current: self.other,
@@ -41,10 +245,87 @@ impl Board {
         return self.other.wins_involving(move_);
     }
 
+    // Returns the moves that win immediately.
+    pub fn winning_moves(self) -> BitBoard {
+        return self.current.threats(self.other).intersect(self.moves());
+    }
+
+    // Returns the moves that block an immediate win of the opponent.
+    pub fn blocking_moves(self) -> BitBoard {
+        return self.other.threats(self.current).intersect(self.moves());
+    }
+
+    // Returns the number of threats the current player has after making move_.
+    pub fn threats_after(self, move_: BitBoard) -> u32 {
+        return self.current.do_move(move_).threats(self.other).count();
+    }
+
+    // Stones for the player that will make the next move.
+    pub fn current(self) -> BitBoard {
+        return self.current;
+    }
+
+    // Stones for the player that made the last move.
+    pub fn other(self) -> BitBoard {
+        return self.other;
+    }
+
     pub fn raw(self) -> (u64, u64) {
         return (self.current.raw(), self.other.raw());
     }
 
+    // Returns a 49-bit key that is unique for the position.
+    pub fn key(self) -> u64 {
+        return self.current.key(self.other);
+    }
+
+    // Returns the 49-bit encoding that is common for 7x6 boards: 7 bits per
+    // column (starting at the least significant bits with the first column),
+    // holding current + mask + bottom, where `mask` has all stones and
+    // `bottom` the lowest place of every column. It's the same as key().
+    pub fn encode(self) -> u64 {
+        return self.key();
+    }
+
+    // Returns the position as a base-3 number with a digit for every place,
+    // where the digit for (col, row) is the (col * 6 + row)-th digit: 0 if the
+    // place is empty, 1 for a stone of the first player and 2 for the second.
+    pub fn to_base3(self) -> u128 {
+        let (first, second) = if self.side_to_move() == FIRST_PLAYER {
+            (self.current, self.other)
+        } else {
+            (self.other, self.current)
+        };
+        let mut number = 0;
+        for col in (0..7).rev() {
+            for row in (0..6).rev() {
+                let digit = if first.is_set(col, row) {
+                    1
+                } else if second.is_set(col, row) {
+                    2
+                } else {
+                    0
+                };
+                number = number * 3 + digit;
+            }
+        }
+        return number;
+    }
+
+    // Returns a 64-bit hash of the position, for use outside of the solver
+    // (opening books, databases, removing duplicates). It's the murmur
+    // finalizer applied to key(), so it's stable across versions, and
+    // different positions never get the same hash. Nothing has to be updated
+    // in do_move(), it only takes a few operations on the bitboards.
+    pub fn hash(self) -> u64 {
+        return cache::murmur(self.key());
+    }
+
+    // Like hash(), but a position and its mirror image get the same hash.
+    pub fn symmetric_hash(self) -> u64 {
+        return self.canonical().hash();
+    }
+
     pub fn non_losing_moves(self, moves: BitBoard) -> BitBoard {
         return self.other.non_losing_moves(moves);
     }
@@ -60,6 +341,8 @@ impl Board {
         return std::cmp::max(self, self.mirror());
     }
 
+    // Only used by the benchmarks, see canonical().
+    #[allow(dead_code)]
     fn canonical_lazy(self) -> Board {
         let current_mirrored = self.current.mirror();
         if current_mirrored > self.current {
@@ -86,77 +369,461 @@ impl Board {
         };
     }
 
-    // Returns 0 if the first player is to move, 1 if the second player is to move.
-    // This is slow, though it could be faster with a population count.
-    pub fn player(self) -> u64 {
-        let mut stones = 0;
-        for row in (0..6).rev() {
-            for col in 0..7 {
-                if self.current.is_set(col, row) || self.other.is_set(col, row) {
-                    stones += 1;
-                }
+    // Checks that the bitboards make up a position: the stones are stacked in
+    // the 7x6 places, the numbers of stones match, and only the last move may
+    // have completed a four-in-a-row. That last move must be on top of a
+    // column. The position may still be unreachable, see move_sequence().
+    pub fn validate(self) -> Result<(), BoardError> {
+        if !self.current.intersect(self.other).empty() {
+            return Err(BoardError::Overlap);
+        }
+        if !self.current.in_bounds() || !self.other.in_bounds() {
+            return Err(BoardError::OutOfBounds);
+        }
+        if !self.current.stacked(self.other) {
+            return Err(BoardError::Floating);
+        }
+        if !(0..=1).contains(&(self.other.count() as i64 - self.current.count() as i64)) {
+            return Err(BoardError::StoneCount);
+        }
+        if self.current.won() {
+            return Err(BoardError::EarlyFour);
+        }
+        if self.other.won() {
+            // Taking back one of the stones on top must undo all of them.
+            let undoes_fours = |col| match self.column_top(col) {
+                Some(row) => self.other.is_set(col, row) && !self.other.flip(col, row).won(),
+                None => false,
+            };
+            if !(0..7).any(undoes_fours) {
+                return Err(BoardError::EarlyFour);
             }
         }
-        return stones & 1;
+        return Ok(());
     }
 
-    fn print_custom(self, current_token: char, other_token: char) {
-        for row in (0..6).rev() {
-            for col in 0..7 {
-                let c = match (self.current.is_set(col, row), self.other.is_set(col, row)) {
-                    (false, false) => '.',
-                    (true, false) => current_token,
-                    (false, true) => other_token,
-                    (true, true) => '*',
-                };
-                print! {"{}", c};
+    // Returns the row of the highest stone in a column, if any.
+    fn column_top(self, col: u64) -> Option<u64> {
+        return (0..6)
+            .rev()
+            .find(|row| self.current.is_set(col, *row) || self.other.is_set(col, *row));
+    }
+
+    // Returns the columns (0 to 6) of a game that leads to this position, or
+    // None if the position isn't valid (see validate()) or can't be reached
+    // in a game.
+    pub fn move_sequence(self) -> Option<Vec<u64>> {
+        if self.validate().is_err() {
+            return None;
+        }
+        let mut moves = Vec::new();
+        if !self.take_back(&mut moves, &mut HashSet::new()) {
+            return None;
+        }
+        return Some(moves);
+    }
+
+    // Takes back the moves of a game that leads to this position, and pushes
+    // them onto `moves` in the order they were played. Returns false if there
+    // is no such game. `dead_ends` has the keys of positions that were tried already.
+    fn take_back(self, moves: &mut Vec<u64>, dead_ends: &mut HashSet<u64>) -> bool {
+        if self.other.empty() {
+            return self.current.empty();
+        }
+        if dead_ends.contains(&self.key()) {
+            return false;
+        }
+        for col in 0..7 {
+            let top = match self.column_top(col) {
+                Some(row) => row,
+                None => continue,
+            };
+            if !self.other.is_set(col, top) {
+                continue;
+            }
+            // The position before the last move can't have a four-in-a-row.
+            let previous = Board {
+                current: self.other.flip(col, top),
+                other: self.current,
+            };
+            if !previous.current.won() && previous.take_back(moves, dead_ends) {
+                moves.push(col);
+                return true;
             }
-            println!();
         }
+        dead_ends.insert(self.key());
+        return false;
     }
 
-    // print always prints an X for the first player, and an O for the second player.
-    pub fn print(self) {
-        let player = self.player();
-        if player == FIRST_PLAYER {
-            self.print_custom('X', 'O');
-        } else {
-            self.print_custom('O', 'X');
+    // Returns the result if the game is over: the last move completed a
+    // four-in-a-row, or the board is full.
+    pub fn outcome(self) -> Option<GameResult> {
+        if self.other.won() {
+            // The player that made the last move won.
+            if self.side_to_move() == SECOND_PLAYER {
+                return Some(GameResult::FirstPlayerWins);
+            }
+            return Some(GameResult::SecondPlayerWins);
+        }
+        if self.is_full() {
+            return Some(GameResult::Draw);
         }
+        return None;
+    }
+
+    // Returns the (col, row) places of a four-in-a-row of the player that made
+    // the last move, if there is one.
+    pub fn winning_line(self) -> Option<[(u64, u64); 4]> {
+        // Right, up, diagonally up and diagonally down.
+        for (dcol, drow) in [(1, 0), (0, 1), (1, 1), (1, -1)] {
+            for col in 0..7 {
+                for row in 0..6 {
+                    let line = [0, 1, 2, 3].map(|i| (col + dcol * i, row + drow * i));
+                    let in_line = |(col, row): (i64, i64)| {
+                        (0..7).contains(&col)
+                            && (0..6).contains(&row)
+                            && self.other.is_set(col as u64, row as u64)
+                    };
+                    if line.iter().all(|place| in_line(*place)) {
+                        return Some(line.map(|(col, row)| (col as u64, row as u64)));
+                    }
+                }
+            }
+        }
+        return None;
+    }
+
+    // Gives the stone at (col, row) to the other player. The result usually
+    // can't be reached in a game, it's used to verify cache key reductions.
+    pub fn swap_stone(self, col: u64, row: u64) -> Board {
+        return Board {
+            current: self.current.flip(col, row),
+            other: self.other.flip(col, row),
+        };
+    }
+
+    // Returns the number of stones on the board, which is the number of moves
+    // made so far.
+    pub fn ply(self) -> u64 {
+        return (self.current.raw() | self.other.raw()).count_ones() as u64;
+    }
+
+    // Same as ply().
+    pub fn moves_played(self) -> u64 {
+        return self.ply();
+    }
+
+    pub fn is_full(self) -> bool {
+        return self.ply() == 42;
+    }
+
+    // Returns FIRST_PLAYER or SECOND_PLAYER.
+    pub fn side_to_move(self) -> u64 {
+        return self.ply() & 1;
+    }
+
+    // Renders the position in the ASCII style (an X for the first player and an
+    // O for the second player), with column numbers (starting at 1) below it.
+    // Places in `highlight` are surrounded by parentheses.
+    pub fn render(self, highlight: BitBoard) -> String {
+        return render::render(self, render::Style::Ascii, highlight);
     }
 }
 
+// The same grid as Board::render() without a highlight.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return f.write_str(&self.render(bitboard::empty()));
+    }
+}
+
+// The raw bitboards, followed by the grid on the next lines.
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f,
+            "Board {{ current: {:#x}, other: {:#x} }}\n{}",
+            self.current.raw(),
+            self.other.raw(),
+            self
+        );
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
-    const ITERATION_COUNT: u64 = 10;
-
-    #[bench]
-    fn bench_canonical_max(b: &mut test::Bencher) {
-        b.iter(|| {
-            for i in 0..ITERATION_COUNT {
-                for j in 0..ITERATION_COUNT {
-                    let board = Board {
-                        current: bitboard::random(i),
-                        other: bitboard::random(j),
-                    };
-                    test::black_box(board.canonical_max());
+    use verify;
+
+    #[test]
+    fn test_hash() {
+        // The hash is stable, so these must never change.
+        assert_eq!(empty().hash(), 0x3cd6666812fa3ded);
+        assert_eq!(from_moves("4453").unwrap().hash(), 0x890c17aa3f20b4e1);
+
+        let mut seed = 1;
+        let mut hashes = std::collections::HashMap::new();
+        for depth in 0..30 {
+            for _ in 0..100 {
+                let (_, board) = verify::random_position(depth, &mut seed);
+                assert_eq!(board.symmetric_hash(), board.mirror().symmetric_hash());
+                if let Some(other) = hashes.insert(board.hash(), board) {
+                    assert_eq!(other, board);
                 }
             }
-        });
+        }
     }
 
-    #[bench]
-    fn bench_canonical_lazy(b: &mut test::Bencher) {
-        b.iter(|| {
-            for i in 0..ITERATION_COUNT {
-                for j in 0..ITERATION_COUNT {
-                    let board = Board {
-                        current: bitboard::random(i),
-                        other: bitboard::random(j),
-                    };
-                    test::black_box(board.canonical_lazy());
-                }
+    #[test]
+    fn test_encodings() {
+        assert_eq!(empty().encode(), 0x40810204081);
+        assert_eq!(empty().to_base3(), 0);
+        // A stone of the second player on top of one of the first player.
+        assert_eq!(from_moves("11").unwrap().to_base3(), 1 + 2 * 3);
+        let mut seed = 2;
+        for depth in 0..=42 {
+            for _ in 0..20 {
+                let (moves, board) = verify::random_position(depth, &mut seed);
+                assert_eq!(decode(board.encode()), Some(board));
+                assert_eq!(from_base3(board.to_base3()), Some(board));
+                assert_eq!(format_moves(&parse_moves(&moves).unwrap()), moves);
+            }
+        }
+
+        // No marker bit in the last column.
+        assert!(decode(0x40810204081 & !(1 << 42)).is_none());
+        assert!(decode(1 << 49).is_none());
+        // Two stones of the player to move in the first column.
+        assert!(decode(0x40810204080 | 0b111).is_none());
+        // A stone above an empty place.
+        assert!(from_base3(3).is_none());
+        // Two stones of the first player.
+        assert!(from_base3(1 + 3).is_none());
+        assert!(from_base3(3u128.pow(42)).is_none());
+        assert_eq!(parse_moves("4453"), Some(vec![3, 3, 4, 2]));
+        assert_eq!(parse_moves("408"), None);
+    }
+
+    // Returns a board from (col, row) places of both players.
+    fn from_places(current: &[(u64, u64)], other: &[(u64, u64)]) -> Board {
+        let mut board = empty();
+        for (col, row) in current {
+            board.current = board.current.flip(*col, *row);
+        }
+        for (col, row) in other {
+            board.other = board.other.flip(*col, *row);
+        }
+        return board;
+    }
+
+    #[test]
+    fn test_ply() {
+        let mut seed = 5;
+        for depth in 0..=42 {
+            let (_, board) = verify::random_position(depth, &mut seed);
+            assert_eq!(board.ply(), depth);
+            assert_eq!(board.moves_played(), depth);
+            assert_eq!(board.is_full(), depth == 42);
+            assert_eq!(board.side_to_move(), depth % 2);
+        }
+    }
+
+    #[test]
+    fn test_outcome() {
+        assert_eq!(empty().outcome(), None);
+        assert_eq!(empty().winning_line(), None);
+        let board = from_moves("4455667").unwrap();
+        assert_eq!(board.outcome(), Some(GameResult::FirstPlayerWins));
+        assert_eq!(board.winning_line(), Some([(3, 0), (4, 0), (5, 0), (6, 0)]));
+        let board = from_moves("21212131").unwrap();
+        assert_eq!(board.outcome(), Some(GameResult::SecondPlayerWins));
+        assert_eq!(board.winning_line(), Some([(0, 0), (0, 1), (0, 2), (0, 3)]));
+        let board = from_moves("12234334474").unwrap();
+        assert_eq!(board.outcome(), Some(GameResult::FirstPlayerWins));
+        assert_eq!(board.winning_line(), Some([(0, 0), (1, 1), (2, 2), (3, 3)]));
+        let board = from_moves("76654554414").unwrap();
+        assert_eq!(board.winning_line(), Some([(3, 3), (4, 2), (5, 1), (6, 0)]));
+
+        let mut seed = 6;
+        for depth in 0..=42 {
+            let (_, board) = verify::random_position(depth, &mut seed);
+            let expected = if depth == 42 {
+                Some(GameResult::Draw)
+            } else {
+                None
+            };
+            assert_eq!(board.outcome(), expected);
+            assert_eq!(board.winning_line(), None);
+        }
+    }
+
+    #[test]
+    fn test_play() {
+        let board = from_moves("444444").unwrap();
+        assert_eq!(board.column_height(3), Some(6));
+        assert_eq!(board.column_height(0), Some(0));
+        assert_eq!(board.column_height(7), None);
+        assert_eq!(
+            board.legal_columns().collect::<Vec<u64>>(),
+            vec![0, 1, 2, 4, 5, 6]
+        );
+        assert_eq!(board.play(3), Err(MoveError::ColumnFull));
+        assert_eq!(board.play(7), Err(MoveError::InvalidColumn));
+        assert_eq!(board.play(0).unwrap(), from_moves("4444441").unwrap());
+
+        let won = from_moves("4455667").unwrap();
+        assert_eq!(won.play(0), Err(MoveError::GameOver));
+        assert_eq!(won.legal_columns().count(), 0);
+        let mut seed = 7;
+        let (_, full) = verify::random_position(42, &mut seed);
+        assert_eq!(full.play(0), Err(MoveError::GameOver));
+        assert_eq!(full.legal_columns().count(), 0);
+
+        let mut stack = move_stack(empty());
+        assert_eq!(stack.undo(), None);
+        assert!(stack.last_move().empty());
+        stack.play(3).unwrap();
+        stack.play(3).unwrap();
+        assert_eq!(stack.play(9), Err(MoveError::InvalidColumn));
+        assert_eq!(stack.columns(), &[3, 3]);
+        assert_eq!(stack.last_move().raw(), bitboard::empty().flip(3, 1).raw());
+        assert_eq!(stack.undo(), Some(3));
+        assert_eq!(stack.board(), from_moves("4").unwrap());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut seed = 4;
+        for depth in 0..=42 {
+            let (_, board) = verify::random_position(depth, &mut seed);
+            assert_eq!(board.validate(), Ok(()));
+        }
+        assert_eq!(from_moves("4455667").unwrap().validate(), Ok(()));
+
+        assert_eq!(
+            from_places(&[(0, 0)], &[(0, 0)]).validate(),
+            Err(BoardError::Overlap)
+        );
+        let mut separator = empty();
+        separator.other = bitboard::random(1 << 6);
+        assert_eq!(separator.validate(), Err(BoardError::OutOfBounds));
+        assert_eq!(
+            from_places(&[], &[(0, 1)]).validate(),
+            Err(BoardError::Floating)
+        );
+        assert_eq!(
+            from_places(&[], &[(0, 0), (1, 0)]).validate(),
+            Err(BoardError::StoneCount)
+        );
+        assert_eq!(
+            from_places(&[(0, 0)], &[]).validate(),
+            Err(BoardError::StoneCount)
+        );
+        // The player to move has a four-in-a-row.
+        let current_four = from_places(
+            &[(0, 0), (1, 0), (2, 0), (3, 0)],
+            &[(0, 1), (1, 1), (2, 1), (4, 0)],
+        );
+        assert_eq!(current_four.validate(), Err(BoardError::EarlyFour));
+        let two_fours = from_places(
+            &[(1, 0), (1, 1), (1, 2), (4, 0), (4, 1), (4, 2), (6, 0)],
+            &[
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (0, 3),
+                (2, 0),
+                (2, 1),
+                (2, 2),
+                (2, 3),
+            ],
+        );
+        assert_eq!(two_fours.validate(), Err(BoardError::EarlyFour));
+    }
+
+    #[test]
+    fn test_move_sequence() {
+        let mut seed = 3;
+        for depth in 0..=42 {
+            for _ in 0..20 {
+                let (_, board) = verify::random_position(depth, &mut seed);
+                let moves = board.move_sequence().unwrap();
+                assert_eq!(from_moves(&format_moves(&moves)), Some(board));
             }
-        });
+        }
+        // The last move may complete a four-in-a-row.
+        let won = from_moves("4455667").unwrap();
+        assert_eq!(
+            from_moves(&format_moves(&won.move_sequence().unwrap())),
+            Some(won)
+        );
+
+        // A floating stone.
+        assert_eq!(from_places(&[], &[(0, 1)]).move_sequence(), None);
+        // Too many stones of the last player.
+        assert_eq!(from_places(&[], &[(0, 0), (1, 0)]).move_sequence(), None);
+        // Both players have a stone in the same place.
+        assert_eq!(from_places(&[(0, 0)], &[(0, 0)]).move_sequence(), None);
+        // Two fours-in-a-row of the last player, so one was completed earlier.
+        let two_fours = from_places(
+            &[(1, 0), (1, 1), (1, 2), (4, 0), (4, 1), (4, 2), (6, 0)],
+            &[
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (0, 3),
+                (2, 0),
+                (2, 1),
+                (2, 2),
+                (2, 3),
+            ],
+        );
+        assert_eq!(two_fours.move_sequence(), None);
+        // The four-in-a-row can't be completed last, since the stone above it
+        // was played later.
+        let covered = from_places(
+            &[(1, 0), (1, 1), (1, 2), (0, 4)],
+            &[(0, 0), (0, 1), (0, 2), (0, 3), (2, 0)],
+        );
+        assert_eq!(covered.move_sequence(), None);
+    }
+
+    // Benchmarks need nightly Rust.
+    #[cfg(feature = "nightly")]
+    mod benches {
+        extern crate test;
+
+        use super::*;
+        const ITERATION_COUNT: u64 = 10;
+
+        #[bench]
+        fn bench_canonical_max(b: &mut test::Bencher) {
+            b.iter(|| {
+                for i in 0..ITERATION_COUNT {
+                    for j in 0..ITERATION_COUNT {
+                        let board = Board {
+                            current: bitboard::random(i),
+                            other: bitboard::random(j),
+                        };
+                        test::black_box(board.canonical_max());
+                    }
+                }
+            });
+        }
+
+        #[bench]
+        fn bench_canonical_lazy(b: &mut test::Bencher) {
+            b.iter(|| {
+                for i in 0..ITERATION_COUNT {
+                    for j in 0..ITERATION_COUNT {
+                        let board = Board {
+                            current: bitboard::random(i),
+                            other: bitboard::random(j),
+                        };
+                        test::black_box(board.canonical_lazy());
+                    }
+                }
+            });
+        }
     }
 }
@@ -3,8 +3,28 @@ use board::Board;
 const CACHE_USED_BIT: u64 = 1 << 63; // Used on CacheBoard.first
 const CACHE_OUTCOME_BIT: u64 = 1 << 63; // Used on CacheBoard.second
 
+// Used on compact entries, next to the remaining key bits (at most 49).
+const COMPACT_USED_BIT: u64 = 1 << 63;
+const COMPACT_OUTCOME_BIT: u64 = 1 << 62;
+
+const KEY_MASK: u64 = (1 << 49) - 1;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    // 16 byte entries that store both (reduced) bitboards.
+    Full,
+    // 8 byte entries that store a 49-bit position key, minus the bits that are
+    // implied by the index of the entry. This fits twice as many entries in the
+    // same amount of memory.
+    Compact,
+}
+
 pub struct Cache {
+    format: Format,
+    // Whether Full entries use with_color_less(), see set_color_less().
+    color_less: bool,
     cache: Vec<CacheBoard>,
+    compact: Vec<u64>,
 }
 
 #[derive(Copy, Clone)]
@@ -13,9 +33,29 @@ pub struct CacheBoard {
     second: u64,
 }
 
-pub fn new(log_size: u64) -> Cache {
-    return Cache {
-        cache: vec![empty(); 1 << log_size],
+// Creates a cache with 2^log_size entries.
+pub fn new(log_size: u64, format: Format) -> Cache {
+    return match format {
+        Format::Full => Cache {
+            format,
+            color_less: true,
+            cache: vec![empty(); 1 << log_size],
+            compact: Vec::new(),
+        },
+        Format::Compact => Cache {
+            format,
+            color_less: false,
+            cache: Vec::new(),
+            compact: vec![0; 1 << log_size],
+        },
+    };
+}
+
+// Returns the log size of a cache in the given format that takes up 2^log_bytes bytes.
+pub fn log_size_for_bytes(log_bytes: u64, format: Format) -> u64 {
+    return match format {
+        Format::Full => log_bytes - 4,
+        Format::Compact => log_bytes - 3,
     };
 }
 
@@ -27,6 +67,25 @@ pub fn board(board: Board) -> CacheBoard {
     };
 }
 
+// Like board(), but without with_color_less().
+fn mirrored_board(board: Board) -> CacheBoard {
+    let (b1, b2) = board.canonical().raw();
+    return CacheBoard {
+        first: b1 | CACHE_USED_BIT,
+        second: b2,
+    };
+}
+
+// Like board(), but for the compact format. The 49-bit key can't represent
+// stones that belong to both players after with_color_less(), so only mirroring
+// is applied.
+fn compact_board(board: Board) -> CacheBoard {
+    return CacheBoard {
+        first: mix49(board.canonical().key()),
+        second: 0,
+    };
+}
+
 fn empty() -> CacheBoard {
     return CacheBoard {
         first: 0,
@@ -34,7 +93,9 @@ fn empty() -> CacheBoard {
     };
 }
 
-fn murmur(hash: u64) -> u64 {
+// The finalizer of MurmurHash3, a bijection on 64-bit numbers that mixes all
+// bits. Board::hash() depends on it, so it must not change.
+pub fn murmur(hash: u64) -> u64 {
     let mut h = std::num::Wrapping(hash); // Overflow is on purpose here.
     h ^= h >> 33;
     h *= 0xff51afd7ed558ccd;
@@ -44,14 +105,82 @@ fn murmur(hash: u64) -> u64 {
     return h.0;
 }
 
+// A bijection on 49-bit numbers. Every step is invertible modulo 2^49, so the
+// index and the stored bits of a compact entry together still identify the
+// position exactly.
+fn mix49(key: u64) -> u64 {
+    let mut h = key;
+    h ^= h >> 25;
+    h = h.wrapping_mul(0xff51afd7ed558ccd) & KEY_MASK;
+    h ^= h >> 24;
+    h = h.wrapping_mul(0xc4ceb9fe1a85ec53) & KEY_MASK;
+    h ^= h >> 25;
+    return h;
+}
+
 impl Cache {
+    pub fn format(&self) -> Format {
+        return self.format;
+    }
+
+    // Turns the with_color_less() reduction of Full entries on (the default)
+    // or off, e.g. to measure how much it helps. Compact entries never use it.
+    // Entries stored before the change are no longer found, so clear() first.
+    pub fn set_color_less(&mut self, color_less: bool) {
+        self.color_less = color_less && self.format == Format::Full;
+    }
+
+    // Removes all entries.
+    pub fn clear(&mut self) {
+        self.cache.fill(empty());
+        self.compact.fill(0);
+    }
+
+    // Computes the cache board for the format of this cache.
+    pub fn board(&self, board: Board) -> CacheBoard {
+        return match self.format {
+            Format::Full if self.color_less => self::board(board),
+            Format::Full => mirrored_board(board),
+            Format::Compact => compact_board(board),
+        };
+    }
+
     fn key(&self, board: CacheBoard) -> usize {
         let b1 = murmur(board.first);
         let b2 = murmur(board.second);
         return ((b1 ^ b2) as usize) & (self.cache.len() - 1);
     }
 
+    fn compact_key(&self, board: CacheBoard) -> (usize, u64) {
+        let log_size = self.compact.len().trailing_zeros();
+        let index = (board.first as usize) & (self.compact.len() - 1);
+        return (index, (board.first >> log_size) | COMPACT_USED_BIT);
+    }
+
+    // Hints the CPU to load the entry for `board`, so a later lookup() doesn't
+    // have to wait for main memory.
+    pub fn prefetch(&self, board: CacheBoard) {
+        let entry = match self.format {
+            Format::Full => &self.cache[self.key(board)] as *const CacheBoard as *const i8,
+            Format::Compact => &self.compact[self.compact_key(board).0] as *const u64 as *const i8,
+        };
+        #[cfg(target_arch = "x86_64")]
+        unsafe {
+            std::arch::x86_64::_mm_prefetch::<{ std::arch::x86_64::_MM_HINT_T0 }>(entry);
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        let _ = entry;
+    }
+
     pub fn lookup(&mut self, board: CacheBoard) -> Option<bool> {
+        if self.format == Format::Compact {
+            let (key, stored) = self.compact_key(board);
+            let value = self.compact[key];
+            if value & !COMPACT_OUTCOME_BIT == stored {
+                return Some(value & COMPACT_OUTCOME_BIT != 0);
+            }
+            return None::<bool>;
+        }
         let key = self.key(board);
         let value = self.cache[key];
         if value.first == board.first && value.second & !CACHE_OUTCOME_BIT == board.second {
@@ -61,6 +190,14 @@ impl Cache {
     }
 
     pub fn store(&mut self, mut board: CacheBoard, result: bool) {
+        if self.format == Format::Compact {
+            let (key, mut stored) = self.compact_key(board);
+            if result {
+                stored |= COMPACT_OUTCOME_BIT;
+            }
+            self.compact[key] = stored;
+            return;
+        }
         let key = self.key(board);
         if result {
             board.second |= CACHE_OUTCOME_BIT;
@@ -68,3 +205,48 @@ impl Cache {
         self.cache[key] = board;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board;
+
+    // All positions after exactly `depth` moves, with mirrored duplicates removed.
+    fn positions(depth: u64) -> Vec<Board> {
+        let mut result = vec![board::empty()];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for board in result {
+                let moves = board.moves();
+                for col in 0..7 {
+                    let move_ = moves.for_column(col);
+                    if !move_.empty() {
+                        next.push(board.do_move(move_).canonical());
+                    }
+                }
+            }
+            next.sort();
+            next.dedup();
+            result = next;
+        }
+        return result;
+    }
+
+    #[test]
+    fn test_compact_no_collisions() {
+        let boards = positions(4);
+        let result = |i: usize| i.count_ones().is_multiple_of(2);
+        let mut cache = new(4, Format::Compact);
+        for (i, b) in boards.iter().enumerate() {
+            cache.store(cache.board(*b), result(i));
+            assert_eq!(cache.lookup(cache.board(b.mirror())), Some(result(i)));
+            // Any hit must be a position that was stored before, with its own result.
+            for (j, other) in boards.iter().enumerate() {
+                if let Some(r) = cache.lookup(cache.board(*other)) {
+                    assert!(j <= i);
+                    assert_eq!(r, result(j));
+                }
+            }
+        }
+    }
+}
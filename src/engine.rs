@@ -0,0 +1,298 @@
+// A line based protocol in the spirit of UCI, so the solver can be used by
+// board game GUIs. The GUI sends these commands:
+//
+//   uci                          The engine replies with its name, options and "uciok".
+//   isready                      The engine replies with "readyok".
+//   setoption name <id> value <x>
+//                                Sets an option: "Hash" is the size of the caches
+//                                in MB, "CacheFormat" is "full" or "compact".
+//   ucinewgame                   Clears the caches.
+//   position startpos [moves <columns>]
+//                                Sets the position. Columns are 1 to 7, separated
+//                                by spaces or not, e.g. "moves 4 4 5 3" or "moves 4453".
+//   go                           Starts solving the position, in the background.
+//   stop                         Stops solving, the engine replies with "bestmove".
+//   quit                         Stops the engine.
+//
+// While solving, the engine sends:
+//
+//   info currmove <column> score <score>
+//                                Once the score of a column is known (see
+//                                Solver::score()).
+//   info score <score> nodes <nodes> time <milliseconds>
+//                                Once the score of the position is known.
+//   bestmove <column>            When done or stopped. If stopped early, the best
+//                                column found so far. "bestmove none" means the
+//                                game is over.
+//
+// Errors are reported as "info string <message>".
+use std::io;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use board;
+use board::Board;
+use cache;
+use solver;
+use solver::Solver;
+
+// The most memory the solver may use, in MB, also for --cache-size.
+pub const MAX_HASH_MB: u64 = 1 << 16;
+
+// Columns in the order in which they are solved.
+const COLUMN_ORDER: [u64; 7] = [3, 2, 4, 1, 5, 0, 6];
+
+fn send<W: Write>(output: &Mutex<W>, line: &str) -> io::Result<()> {
+    let mut output = output.lock().unwrap();
+    writeln!(output, "{}", line)?;
+    return output.flush();
+}
+
+struct Engine<W> {
+    // Shared with the search thread.
+    output: Arc<Mutex<W>>,
+    hash_mb: u64,
+    format: cache::Format,
+    // See Solver::set_color_less().
+    color_less: bool,
+    // Created when it's first needed, so the options can be set before
+    // allocating the caches. None while a search is running.
+    solver: Option<Solver>,
+    // Returns the solver when the search is done.
+    search: Option<thread::JoinHandle<Solver>>,
+    // The stop flag of the solver of the running search.
+    stop_flag: Option<Arc<AtomicBool>>,
+    // Set by the running search before it sends "bestmove".
+    search_done: Option<Arc<AtomicBool>>,
+    board: Board,
+}
+
+impl<W: Write + Send + 'static> Engine<W> {
+    // Waits for the running search to finish, if any.
+    fn wait(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.solver = Some(search.join().unwrap());
+            self.stop_flag = None;
+            self.search_done = None;
+        }
+    }
+
+    // Waits for the running search, if any, and returns the solver.
+    fn solver(&mut self) -> &mut Solver {
+        self.wait();
+        if self.solver.is_none() {
+            let mut solver = solver::with_megabytes(self.hash_mb, self.format);
+            solver.set_color_less(self.color_less);
+            self.solver = Some(solver);
+        }
+        return self.solver.as_mut().unwrap();
+    }
+
+    // Aborts the running search, if any, and waits for it.
+    fn stop(&mut self) {
+        if let Some(stop_flag) = &self.stop_flag {
+            // The flag is cleared again before the next search.
+            stop_flag.store(true, Ordering::Relaxed);
+        }
+        self.wait();
+    }
+
+    fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        match name {
+            "Hash" => match value.parse() {
+                Ok(mb) if (1..=MAX_HASH_MB).contains(&mb) => self.hash_mb = mb,
+                _ => return send(&self.output, "info string Invalid value for Hash"),
+            },
+            "CacheFormat" => match value {
+                "full" => self.format = cache::Format::Full,
+                "compact" => self.format = cache::Format::Compact,
+                _ => return send(&self.output, "info string Invalid value for CacheFormat"),
+            },
+            _ => {
+                return send(
+                    &self.output,
+                    &format!("info string Unknown option {}", name),
+                )
+            }
+        }
+        // The solver is created again with the new options when needed.
+        self.stop();
+        self.solver = None;
+        return Ok(());
+    }
+
+    fn set_position(&mut self, args: &[&str]) -> io::Result<()> {
+        let moves = match args {
+            ["startpos"] => String::new(),
+            ["startpos", "moves", columns @ ..] => columns.concat(),
+            _ => return send(&self.output, "info string Invalid position"),
+        };
+        match board::from_moves(&moves) {
+            Some(board) => self.board = board,
+            None => return send(&self.output, "info string Invalid moves"),
+        }
+        return Ok(());
+    }
+
+    fn go(&mut self) -> io::Result<()> {
+        // A search that is done has sent (or is sending) its "bestmove", so
+        // only a search that is still running is in the way.
+        if let Some(search_done) = &self.search_done {
+            if search_done.load(Ordering::Relaxed) {
+                self.wait();
+            }
+        }
+        if self.search.is_some() {
+            return send(&self.output, "info string Already searching");
+        }
+        let stop_flag = self.solver().stop_flag();
+        stop_flag.store(false, Ordering::Relaxed);
+        self.stop_flag = Some(stop_flag);
+        let search_done = Arc::new(AtomicBool::new(false));
+        self.search_done = Some(search_done.clone());
+        let mut solver = self.solver.take().unwrap();
+        let output = self.output.clone();
+        let board = self.board;
+        self.search = Some(thread::spawn(move || {
+            // There's no one to report write errors to.
+            let _ = search(&mut solver, board, &output, &search_done);
+            return solver;
+        }));
+        return Ok(());
+    }
+
+    // Returns false if the engine should quit.
+    fn command(&mut self, line: &str) -> io::Result<bool> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => {}
+            ["uci"] => {
+                send(&self.output, "id name connect4-rust")?;
+                send(
+                    &self.output,
+                    &format!(
+                        "option name Hash type spin default {} min 1 max {}",
+                        self.hash_mb, MAX_HASH_MB
+                    ),
+                )?;
+                send(
+                    &self.output,
+                    "option name CacheFormat type combo default full var full var compact",
+                )?;
+                send(&self.output, "uciok")?;
+            }
+            ["isready"] => send(&self.output, "readyok")?,
+            ["setoption", "name", name, "value", value] => self.set_option(name, value)?,
+            ["ucinewgame"] => {
+                self.stop();
+                self.solver().clear();
+            }
+            ["position", args @ ..] => {
+                self.stop();
+                self.set_position(args)?;
+            }
+            ["go", ..] => self.go()?,
+            ["stop"] => self.stop(),
+            ["quit"] => {
+                self.stop();
+                return Ok(false);
+            }
+            _ => send(
+                &self.output,
+                &format!("info string Unknown command {}", line),
+            )?,
+        }
+        return Ok(true);
+    }
+}
+
+// Solves the columns of the position one by one and reports the best column.
+// Sets `done` before sending "bestmove", so the next "go" can start then.
+fn search<W: Write>(
+    solver: &mut Solver,
+    board: Board,
+    output: &Mutex<W>,
+    done: &AtomicBool,
+) -> io::Result<()> {
+    let start = Instant::now();
+    let moves_examined = solver.moves_examined;
+    let mut scores = [None; 7];
+    if !board.other().won() {
+        for col in COLUMN_ORDER {
+            let score = match solver.score_column(board, col) {
+                Some(score) => score,
+                None => continue,
+            };
+            if solver.stopped() {
+                break;
+            }
+            scores[col as usize] = Some(score);
+            send(
+                output,
+                &format!("info currmove {} score {}", col + 1, score),
+            )?;
+        }
+    }
+    if !solver.stopped() {
+        if let Some(col) = solver::best_column(scores) {
+            send(
+                output,
+                &format!(
+                    "info score {} nodes {} time {}",
+                    scores[col as usize].unwrap(),
+                    solver.moves_examined - moves_examined,
+                    start.elapsed().as_millis()
+                ),
+            )?;
+        }
+    }
+    let best = solver::best_column(scores).or_else(|| {
+        // Stopped before any column was solved.
+        let moves = board.moves();
+        if board.other().won() {
+            return None;
+        }
+        return COLUMN_ORDER
+            .iter()
+            .copied()
+            .find(|col| !moves.for_column(*col).empty());
+    });
+    done.store(true, Ordering::Relaxed);
+    return match best {
+        Some(col) => send(output, &format!("bestmove {}", col + 1)),
+        None => send(output, "bestmove none"),
+    };
+}
+
+// Runs the protocol until "quit" or the end of the input.
+// `hash_mb` and `format` are the defaults of the options.
+pub fn run<R: BufRead, W: Write + Send + 'static>(
+    input: R,
+    output: W,
+    hash_mb: u64,
+    format: cache::Format,
+    color_less: bool,
+) -> io::Result<()> {
+    let mut engine = Engine {
+        output: Arc::new(Mutex::new(output)),
+        hash_mb,
+        format,
+        color_less,
+        solver: None,
+        search: None,
+        stop_flag: None,
+        search_done: None,
+        board: board::empty(),
+    };
+    for line in input.lines() {
+        if !engine.command(&line?)? {
+            return Ok(());
+        }
+    }
+    // Let the last search finish, so input can be piped in.
+    engine.wait();
+    return Ok(());
+}
@@ -0,0 +1,136 @@
+use std::fmt::Write;
+
+use board;
+use board::Board;
+use solver;
+use solver::{Outcome, Solver};
+
+fn player_token(board: Board) -> char {
+    return if board.side_to_move() == board::FIRST_PLAYER {
+        'X'
+    } else {
+        'O'
+    };
+}
+
+// Explains why playing `col` (from 0) in a position loses:
+// it shows the opponent's threats after the move, and the line that follows
+// when both players play their best moves.
+pub fn explain(solver: &mut Solver, board: Board, col: u64) -> String {
+    let mut result = String::new();
+    let move_ = board.moves().for_column(col);
+    if board.other().won() || move_.empty() {
+        writeln!(result, "Column {} can't be played.", col + 1).unwrap();
+        return result;
+    }
+    let player = player_token(board);
+    let moved_board = board.do_move(move_);
+    let score = solver.score_column(board, col).unwrap();
+    match solver::outcome_for_score(score) {
+        Outcome::Win => {
+            writeln!(
+                result,
+                "Column {} wins for {} (score {:+}).",
+                col + 1,
+                player,
+                score
+            )
+            .unwrap();
+            return result;
+        }
+        Outcome::Draw => {
+            writeln!(result, "Column {} draws for {}.", col + 1, player).unwrap();
+            return result;
+        }
+        Outcome::Loss => {}
+    }
+    writeln!(
+        result,
+        "Column {} loses for {} (score {:+}).",
+        col + 1,
+        player,
+        score
+    )
+    .unwrap();
+
+    // After the move, the opponent is the current player.
+    let threats = moved_board.current().threats(moved_board.other());
+    let opponent = player_token(moved_board);
+    if threats.empty() {
+        writeln!(
+            result,
+            "After {}, {} has no threats yet:",
+            col + 1,
+            opponent
+        )
+        .unwrap();
+    } else {
+        writeln!(
+            result,
+            "After {}, {} threatens to win at the marked places:",
+            col + 1,
+            opponent
+        )
+        .unwrap();
+    }
+    result.push_str(&moved_board.render(threats));
+
+    let line = solver.principal_variation(moved_board);
+    let mut final_board = moved_board;
+    let mut last_move = move_;
+    let mut moves = String::new();
+    for c in line.iter() {
+        last_move = final_board.moves().for_column(*c);
+        final_board = final_board.do_move(last_move);
+        moves.push_str(&(c + 1).to_string());
+    }
+    writeln!(
+        result,
+        "Refutation: {} {}, after which {} wins:",
+        col + 1,
+        moves,
+        opponent
+    )
+    .unwrap();
+    result.push_str(&final_board.render(last_move));
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cache;
+
+    #[test]
+    fn test_explain() {
+        let mut solver = solver::new(16, cache::Format::Full);
+        // O has to block at 3 or 7, but loses either way.
+        let board = board::from_moves("44556").unwrap();
+        assert_eq!(
+            explain(&mut solver, board, 6),
+            "Column 7 loses for O (score -18).
+After 7, X threatens to win at the marked places:
+ . . . . . . .
+ . . . . . . .
+ . . . . . . .
+ . . . . . . .
+ . . . O O . .
+ . .(.)X X X O
+ 1 2 3 4 5 6 7
+Refutation: 7 3, after which X wins:
+ . . . . . . .
+ . . . . . . .
+ . . . . . . .
+ . . . . . . .
+ . . . O O . .
+ . .(X)X X X O
+ 1 2 3 4 5 6 7
+"
+        );
+        let full_column = board::from_moves("444444").unwrap();
+        assert_eq!(
+            explain(&mut solver, full_column, 3),
+            "Column 4 can't be played.\n"
+        );
+    }
+}
@@ -0,0 +1,22 @@
+// The solver and the position types, for tools that use them as a library.
+// The commands of the binary (play, analyze, engine, serve, ...) are in
+// main.rs.
+// SIMD and benchmarks aren't stabilized yet, so they need nightly Rust and
+// the "nightly" feature.
+#![cfg_attr(all(test, feature = "nightly"), feature(test))]
+#![cfg_attr(feature = "nightly", feature(portable_simd))]
+
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_test;
+
+pub mod bitboard;
+pub mod board;
+pub mod cache;
+mod parity;
+pub mod render;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod solver;
+pub mod verify;
@@ -1,191 +1,185 @@
-#![feature(test)]
-// Nightly Rust is required because SIMD isn't stabilized yet.
-#![feature(portable_simd)]
+extern crate connect4_rust;
 
 // Avoid musl's default allocator due to lackluster performance
 #[cfg(target_env = "musl")]
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-mod bitboard;
-mod board;
-mod cache;
-
-use board::Board;
-use cache::Cache;
-use std::fs::read_to_string;
-
-const DEBUG_PRINT_DEPTH: u64 = 5;
-const CACHE_DEPTH_SKIP: u64 = 2;
-const MOVE_ORDERING_MAX_DEPTH: u64 = 20;
-
-struct MinimaxState<'cache> {
-    moves_examined: u64,
-    cache: &'cache mut Cache,
-    first_player_can_draw: bool,
+mod analyze;
+mod annotate;
+mod engine;
+mod explain;
+mod play;
+mod server;
+
+use std::io::BufRead;
+
+use connect4_rust::{bitboard, board, cache, solver, verify};
+
+// Command line arguments:
+// `[--cache-format=full|compact] [--cache-size=<MB>] [--kernels=scalar|simd|avx2]
+// [--no-color-less] [command [parameters]]`.
+struct Args {
+    cache_format: cache::Format,
+    // Whether cache keys ignore stones that can't influence the outcome.
+    color_less: bool,
+    // The memory used by the caches of the solver, in MB.
+    cache_size: u64,
+    // The first argument that isn't a flag, if any.
+    command: Option<String>,
+    // The arguments after the command.
+    parameters: Vec<String>,
 }
 
-impl<'cache> MinimaxState<'cache> {
-    // For simplicity, this function returns true if the current player can
-    // force a "succesful" outcome: win or maybe a draw, see below.
-    // If `first_player_can_draw` is true, then drawing is considered a
-    // succesful outcome for the first player to move. Correspondingly,
-    // it is not a succesful outcome for the second player to move.
-    // And vice versa.
-    fn minimax(&mut self, board: Board, depth: u64) -> bool {
-        let mut cache_board = None;
-        if depth % CACHE_DEPTH_SKIP == 0 {
-            cache_board = Some(cache::board(board));
-            match self.cache.lookup(cache_board.unwrap()) {
-                Some(result) => {
-                    return result;
-                }
-                None => {}
-            }
-        }
-
-        let moves = board.moves();
-        if moves.empty() {
-            return self.first_player_can_draw;
-        }
-        let moves = board.non_losing_moves(moves);
-        if moves.empty() {
-            return false;
-        }
-
-        self.moves_examined += 1;
+fn force_kernels(kernels: bitboard::Kernels) {
+    assert!(
+        bitboard::force_kernels(kernels),
+        "{:?} kernels are not supported",
+        kernels
+    );
+}
 
-        let mut col_order = [3, 2, 4, 1, 5, 6, 0];
-        if depth < MOVE_ORDERING_MAX_DEPTH {
-            let mut scores: [u32; 7] = [0; 7];
-            for col in 0..scores.len() {
-                let move_ = moves.for_column(col as u64);
-                if move_.empty() {
-                    scores[col] = 0;
-                } else {
-                    scores[col] = board.wins_involving(move_);
-                }
-                col_order[col] = col;
-                for i in (0..col).rev() {
-                    let s1 = scores[col_order[i + 1]];
-                    let s0 = scores[col_order[i]];
-                    if s1 > s0
-                        || (s1 == s0 && col_order[i + 1].abs_diff(3) < col_order[i].abs_diff(3))
-                    {
-                        col_order.swap(i + 1, i);
-                    }
-                }
-            }
+fn parse_args() -> Args {
+    let mut args = Args {
+        cache_format: cache::Format::Full,
+        color_less: true,
+        cache_size: 1024,
+        command: None,
+        parameters: Vec::new(),
+    };
+    for arg in std::env::args().skip(1) {
+        if args.command.is_some() {
+            args.parameters.push(arg);
+            continue;
         }
-
-        let mut success = false;
-        for col in col_order {
-            let move_ = moves.for_column(col as u64);
-            if move_.empty() {
-                continue;
-            }
-            let moved_board = board.do_move(move_);
-            let moved_result = self.minimax(moved_board, depth + 1);
-            if depth == DEBUG_PRINT_DEPTH {
-                moved_board.print();
-                println!(
-                    "player={}, result={}, examined={}",
-                    moved_board.player(),
-                    moved_result,
-                    self.moves_examined
+        match arg.as_str() {
+            "--cache-format=full" => args.cache_format = cache::Format::Full,
+            "--cache-format=compact" => args.cache_format = cache::Format::Compact,
+            // Forces the bitboard kernels, instead of detecting the fastest.
+            "--kernels=scalar" => force_kernels(bitboard::Kernels::Scalar),
+            "--kernels=simd" => force_kernels(bitboard::Kernels::Simd),
+            "--kernels=avx2" => force_kernels(bitboard::Kernels::Avx2),
+            // Disables the with_color_less() cache key reduction, to measure its benefit.
+            "--no-color-less" => args.color_less = false,
+            _ if arg.starts_with("--cache-size=") => {
+                args.cache_size = arg["--cache-size=".len()..]
+                    .parse()
+                    .expect("Invalid cache size");
+                assert!(
+                    (1..=engine::MAX_HASH_MB).contains(&args.cache_size),
+                    "Invalid cache size"
                 );
             }
-            if !moved_result {
-                success = true;
-                break;
-            }
+            _ if arg.starts_with("--") => panic!("Unknown argument: {}", arg),
+            _ => args.command = Some(arg),
         }
-
-        if cache_board.is_some() {
-            self.cache.store(cache_board.unwrap(), success);
-        }
-        return success;
     }
+    return args;
 }
 
-fn main() {
-    let mut cache = cache::new(26); // 1GB cache.
-    println!(
-        "First player to move can force a win: {}",
-        MinimaxState {
-            moves_examined: 0,
-            cache: &mut cache,
-            first_player_can_draw: false,
-        }
-        .minimax(board::empty(), 0)
-    );
+// Solves the empty board, with a single cache of `megabytes` MB (rounded down
+// to a power of 2).
+fn solve(megabytes: u64, format: cache::Format, color_less: bool) {
+    let log_bytes = 63 - (megabytes << 20).leading_zeros() as u64;
+    let mut cache = cache::new(cache::log_size_for_bytes(log_bytes, format), format);
+    cache.set_color_less(color_less);
+    let mut state = solver::MinimaxState::new(&mut cache, false);
+    state.debug_print_depth = Some(solver::DEBUG_PRINT_DEPTH);
+    let result = state.minimax(board::empty());
+    println!("First player to move can force a win: {}", result);
+    println!("Moves examined: {}", state.moves_examined);
 }
 
-mod tests {
-    use std::path::Path;
-
-    use super::*;
+fn new_solver(args: &Args) -> solver::Solver {
+    let mut solver = solver::with_megabytes(args.cache_size, args.cache_format);
+    solver.set_color_less(args.color_less);
+    return solver;
+}
 
-    fn test_positions_from_file(fname: &str) {
-        let mut win_setup = MinimaxState {
-            moves_examined: 0,
-            cache: &mut cache::new(27), // 2GB cache.
-            first_player_can_draw: false,
-        };
-        let mut draw_setup = MinimaxState {
-            moves_examined: 0,
-            cache: &mut cache::new(27), // 2GB cache.
-            first_player_can_draw: true,
-        };
-        // Using the format as described here: http://blog.gamesolver.org/solving-connect-four/02-test-protocol/
-        for line in read_to_string(Path::new("testdata").join(fname))
-            .unwrap()
-            .lines()
-        {
-            println!("{}", line);
-            let line_parts: Vec<_> = line.split(" ").collect();
-            assert_eq!(line_parts.len(), 2);
-            let moves: &str = line_parts[0];
-            let score: i64 = line_parts[1].parse().unwrap();
-            let mut board = board::empty();
-            for mov in moves.as_bytes() {
-                let possible_moves = board.moves();
-                let move_ = possible_moves.for_column((mov - b'1') as u64);
-                assert!(!move_.empty());
-                board = board.do_move(move_);
-            }
-            let first_player_to_move = moves.len() % 2 == 0;
-            let can_force_a_win = (if first_player_to_move {
-                &mut win_setup
-            } else {
-                &mut draw_setup
-            })
-            .minimax(board, moves.len() as u64);
-            if can_force_a_win {
-                assert!(score > 0);
-            } else {
-                let can_force_a_draw = (if first_player_to_move {
-                    &mut draw_setup
-                } else {
-                    &mut win_setup
-                })
-                .minimax(board, moves.len() as u64);
-                if can_force_a_draw {
-                    assert!(score == 0);
-                } else {
-                    assert!(score < 0);
+fn main() {
+    let args = parse_args();
+    match args.command.as_deref() {
+        None => solve(args.cache_size, args.cache_format, args.color_less),
+        Some("play") => {
+            let mut solver = new_solver(&args);
+            let stdin = std::io::stdin();
+            let start_moves = args.parameters.first().map_or("", |moves| moves.as_str());
+            play::run(
+                &mut solver,
+                start_moves,
+                stdin.lock(),
+                &mut std::io::stdout(),
+            )
+            .unwrap();
+        }
+        Some("analyze") => {
+            let moves = args.parameters.first().map_or("", |moves| moves.as_str());
+            let board = board::from_moves(moves).expect("Invalid moves");
+            let mut solver = new_solver(&args);
+            print!("{}", analyze::analyze(&mut solver, board));
+        }
+        Some("explain") => {
+            assert_eq!(args.parameters.len(), 2, "Usage: explain <moves> <column>");
+            let moves = args.parameters[0].as_str();
+            let board = board::from_moves(moves).expect("Invalid moves");
+            let col: u64 = args.parameters[1].parse().expect("Invalid column");
+            assert!((1..=7).contains(&col), "Invalid column");
+            let mut solver = new_solver(&args);
+            print!("{}", explain::explain(&mut solver, board, col - 1));
+        }
+        Some("annotate") => {
+            // Games are read from stdin, one per line.
+            let first_ply = args
+                .parameters
+                .first()
+                .map_or(0, |ply| ply.parse().expect("Invalid ply"));
+            let mut solver = new_solver(&args);
+            for line in std::io::stdin().lock().lines() {
+                let moves = line.unwrap();
+                match annotate::annotate(&mut solver, moves.trim(), first_ply) {
+                    Some(record) => println!("{}", record),
+                    None => println!("[Game \"{}\"]\n[Error \"Invalid move\"]\n", moves.trim()),
                 }
             }
         }
-    }
-
-    #[test]
-    fn test_positions() {
-        test_positions_from_file("Test_L3_R1");
-        test_positions_from_file("Test_L2_R1");
-        test_positions_from_file("Test_L2_R2");
-        test_positions_from_file("Test_L1_R1");
-        test_positions_from_file("Test_L1_R2");
-        test_positions_from_file("Test_L1_R3");
+        Some("engine") => {
+            let stdin = std::io::stdin();
+            engine::run(
+                stdin.lock(),
+                std::io::stdout(),
+                args.cache_size,
+                args.cache_format,
+                args.color_less,
+            )
+            .unwrap();
+        }
+        Some("serve") => {
+            let address = args
+                .parameters
+                .first()
+                .map_or("127.0.0.1:8080", |address| address.as_str());
+            let listener = std::net::TcpListener::bind(address).unwrap();
+            let solver = new_solver(&args);
+            println!("Listening on http://{}", listener.local_addr().unwrap());
+            server::run(listener, solver).unwrap();
+        }
+        Some("verify-color-less") => {
+            let count = args
+                .parameters
+                .first()
+                .map_or(100, |count| count.parse().expect("Invalid count"));
+            let moves = args
+                .parameters
+                .get(1)
+                .map_or(24, |moves| moves.parse().expect("Invalid moves"));
+            assert!(moves < 42, "Invalid moves");
+            let mismatches =
+                verify::verify_color_less(args.cache_size, count, moves, 1, &mut std::io::stdout())
+                    .unwrap();
+            if mismatches > 0 {
+                std::process::exit(1);
+            }
+        }
+        Some(command) => panic!("Unknown command: {}", command),
     }
 }
@@ -0,0 +1,37 @@
+use board::Board;
+
+// Static analysis based on the parity of rows, see Victor Allis, "A Knowledge-based
+// Approach of Connect-Four" (1988). Rows are counted from 0 here, so Allis' odd
+// rows are our even rows.
+//
+// If every column has an even height, the first player is to move and the second
+// player can follow up: always play right above the first player's move. This
+// hands out all empty places on even rows to the first player and all empty
+// places on odd rows to the second player (Allis calls this claimeven). So:
+// - If the first player can't make a four-in-a-row with its stones plus the empty
+//   places on even rows, it can't win.
+// - If, in addition, the second player can make a four-in-a-row with its stones
+//   plus the empty places on odd rows, it wins.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    // The first player (to move) can't win, the second player can at least draw.
+    FirstPlayerCantWin,
+    SecondPlayerWins,
+}
+
+// Returns a verdict if the position is decided (at least partially) by the
+// parity of the rows. Returns None if nothing can be concluded.
+pub fn analyse(board: Board) -> Option<Verdict> {
+    let first = board.current();
+    let second = board.other();
+    if !first.even_heights(second) {
+        return None;
+    }
+    if first.claim_even_rows(second).won() {
+        return None;
+    }
+    if second.claim_odd_rows(first).won() {
+        return Some(Verdict::SecondPlayerWins);
+    }
+    return Some(Verdict::FirstPlayerCantWin);
+}
@@ -0,0 +1,196 @@
+use std::io;
+use std::io::{BufRead, Write};
+
+use board;
+use board::{Board, MoveError, MoveStack};
+use solver;
+use solver::{Outcome, Solver};
+
+const HELP: &str = "Commands:
+  1-7     play a column
+  undo    take back your last move
+  new     start a new game
+  switch  switch sides, the engine moves next
+  help    show this help
+  quit    stop playing
+";
+
+// Game keeps all moves of the current game, so they can be undone.
+struct Game {
+    moves: MoveStack,
+    // board::FIRST_PLAYER or board::SECOND_PLAYER.
+    engine_player: u64,
+}
+
+impl Game {
+    fn board(&self) -> Board {
+        return self.moves.board();
+    }
+
+    fn depth(&self) -> u64 {
+        return self.moves.columns().len() as u64;
+    }
+
+    fn engine_to_move(&self) -> bool {
+        return self.depth() % 2 == self.engine_player;
+    }
+
+    // Returns a message if the game is over.
+    fn result(&self) -> Option<&'static str> {
+        let board = self.board();
+        if board.other().won() {
+            // The player that made the last move won.
+            return Some(if self.engine_to_move() {
+                "You win!"
+            } else {
+                "The engine wins."
+            });
+        }
+        if board.moves().empty() {
+            return Some("Draw.");
+        }
+        return None;
+    }
+}
+
+fn new_game(engine_player: u64) -> Game {
+    return Game {
+        moves: board::move_stack(board::empty()),
+        engine_player,
+    };
+}
+
+fn engine_move<W: Write>(solver: &mut Solver, game: &mut Game, output: &mut W) -> io::Result<()> {
+    writeln!(output, "Thinking...")?;
+    let board = game.board();
+    let outcomes = solver.solve_columns(board);
+    let mut col = solver::best_column(outcomes).unwrap();
+    // Winning moves are all equally good to the solver, but there's no need to
+    // draw out the game. Of the immediate wins, the one closest to the center.
+    let wins_now: [Option<()>; 7] = std::array::from_fn(|win_col| {
+        let move_ = board.moves().for_column(win_col as u64);
+        return (!move_.empty() && board.do_move(move_).other().won()).then_some(());
+    });
+    if let Some(win_col) = solver::best_column(wins_now) {
+        col = win_col;
+    }
+    game.moves.play(col).unwrap();
+    let expectation = match outcomes[col as usize].unwrap() {
+        Outcome::Win => "expects to win",
+        Outcome::Draw => "expects a draw",
+        Outcome::Loss => "expects to lose",
+    };
+    writeln!(output, "The engine plays {} and {}.", col + 1, expectation)?;
+    return Ok(());
+}
+
+// Lets the engine move if it's its turn, then shows the board and the result
+// if the game is over.
+fn advance<W: Write>(solver: &mut Solver, game: &mut Game, output: &mut W) -> io::Result<()> {
+    if game.result().is_none() && game.engine_to_move() {
+        engine_move(solver, game, output)?;
+    }
+    write!(output, "{}", game.board().render(game.moves.last_move()))?;
+    match game.result() {
+        Some(result) => writeln!(output, "{} Type new to play again.", result)?,
+        None => writeln!(output, "Your move (1-7, or help):")?,
+    }
+    return Ok(());
+}
+
+// Runs an interactive game where a human plays against the solver, reading
+// commands from `input` until it ends or `quit` is entered. The game starts
+// after `start_moves` (columns 1-7, e.g. "4453"), with the human to move.
+pub fn run<R: BufRead, W: Write>(
+    solver: &mut Solver,
+    start_moves: &str,
+    input: R,
+    output: &mut W,
+) -> io::Result<()> {
+    let mut game = new_game(board::SECOND_PLAYER);
+    let valid = match board::parse_moves(start_moves) {
+        Some(columns) => columns
+            .into_iter()
+            .all(|col| game.moves.play(col).is_ok() && game.result().is_none()),
+        None => false,
+    };
+    if !valid {
+        writeln!(output, "Invalid start moves: {}", start_moves)?;
+        return Ok(());
+    }
+    // The engine plays the player that doesn't move next.
+    game.engine_player = (game.depth() + 1) % 2;
+    write!(output, "{}", HELP)?;
+    advance(solver, &mut game, output)?;
+    for line in input.lines() {
+        let line = line?;
+        match line.trim() {
+            "" => continue,
+            "quit" => break,
+            "help" => {
+                write!(output, "{}", HELP)?;
+                continue;
+            }
+            "new" => game = new_game(game.engine_player),
+            "switch" => game.engine_player ^= 1,
+            "undo" => {
+                // Undo until it's the human's turn again, with at least one of
+                // the human's moves undone. If the engine moves first, that
+                // move is made again.
+                game.moves.undo();
+                while game.engine_to_move() && game.moves.undo().is_some() {}
+            }
+            command => match command.parse::<u64>() {
+                Ok(col) if (1..=7).contains(&col) => match game.moves.play(col - 1) {
+                    Ok(()) => {}
+                    Err(MoveError::GameOver) => {
+                        writeln!(output, "The game is over, type new or undo.")?;
+                        continue;
+                    }
+                    Err(_) => {
+                        writeln!(output, "Column {} is full.", col)?;
+                        continue;
+                    }
+                },
+                _ => {
+                    writeln!(output, "Invalid command: {}", command)?;
+                    continue;
+                }
+            },
+        }
+        advance(solver, &mut game, output)?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cache;
+    use solver;
+
+    fn play(start_moves: &str, input: &str) -> String {
+        let mut solver = solver::new(16, cache::Format::Full);
+        let mut output = Vec::new();
+        run(&mut solver, start_moves, input.as_bytes(), &mut output).unwrap();
+        return String::from_utf8(output).unwrap();
+    }
+
+    #[test]
+    fn test_human_wins() {
+        let output = play("445566", "7\n3\nundo\n3\n");
+        assert!(output.contains(" . . . O O O .\n . . . X X X(X)\n"));
+        assert!(output.contains("The game is over, type new or undo."));
+        assert!(output.contains(" . .(X)X X X .\n"));
+        assert_eq!(output.matches("You win!").count(), 2);
+    }
+
+    #[test]
+    fn test_engine_wins() {
+        // 3 and 7 both win at once, 3 is closer to the center.
+        let output = play("44556", "1\nquit\n6\n");
+        assert!(output.contains("The engine plays 3 and expects to win."));
+        assert!(output.contains("The engine wins."));
+        assert!(!output.contains("Invalid"));
+    }
+}
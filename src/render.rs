@@ -0,0 +1,193 @@
+// Renders boards and bitboards as text, for the terminal and for debugging
+// the bit tricks (e.g. highlighting the result of almost_wins()).
+use bitboard;
+use bitboard::BitBoard;
+use board;
+use board::Board;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Style {
+    // X for the first player and O for the second player. Highlighted places
+    // are surrounded by parentheses, neighbouring ones share them: "(. .)".
+    Ascii,
+    // Filled discs for the first player and empty discs for the second
+    // player, highlighted like Ascii.
+    Unicode,
+    // Red discs for the first player and yellow discs for the second player,
+    // using ANSI escape codes. Highlighted places have reversed colors.
+    Ansi,
+}
+
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_YELLOW: &str = "\x1b[33m";
+const ANSI_MAGENTA: &str = "\x1b[35m";
+const ANSI_REVERSE: &str = "\x1b[7m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+// What's in a place.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Place {
+    Empty,
+    First,
+    Second,
+    // A stone of both players, see Board::with_color_less().
+    Both,
+}
+
+fn place(first: BitBoard, second: BitBoard, col: u64, row: u64) -> Place {
+    return match (first.is_set(col, row), second.is_set(col, row)) {
+        (false, false) => Place::Empty,
+        (true, false) => Place::First,
+        (false, true) => Place::Second,
+        (true, true) => Place::Both,
+    };
+}
+
+// Returns the grid of places, with column numbers (starting at 1) below it.
+fn grid(style: Style, highlight: BitBoard, place: impl Fn(u64, u64) -> Place) -> String {
+    let mut result = String::new();
+    for row in (0..6).rev() {
+        let mut highlighted = false;
+        for col in 0..7 {
+            let place = place(col, row);
+            if style == Style::Ansi {
+                let color = match place {
+                    Place::Empty => "",
+                    Place::First => ANSI_RED,
+                    Place::Second => ANSI_YELLOW,
+                    Place::Both => ANSI_MAGENTA,
+                };
+                let reverse = if highlight.is_set(col, row) {
+                    ANSI_REVERSE
+                } else {
+                    ""
+                };
+                let token = if place == Place::Empty { '.' } else { '●' };
+                result.push(' ');
+                if color.is_empty() && reverse.is_empty() {
+                    result.push(token);
+                } else {
+                    result.push_str(&format!("{}{}{}{}", color, reverse, token, ANSI_RESET));
+                }
+                continue;
+            }
+            let separator = match (highlighted, highlight.is_set(col, row)) {
+                (false, true) => '(',
+                (true, false) => ')',
+                _ => ' ',
+            };
+            highlighted = highlight.is_set(col, row);
+            result.push(separator);
+            result.push(match (style, place) {
+                (_, Place::Empty) => '.',
+                (Style::Unicode, Place::First) => '●',
+                (Style::Unicode, Place::Second) => '○',
+                (Style::Unicode, Place::Both) => '◍',
+                (_, Place::First) => 'X',
+                (_, Place::Second) => 'O',
+                (_, Place::Both) => '*',
+            });
+        }
+        if highlighted {
+            result.push(')');
+        }
+        result.push('\n');
+    }
+    result.push_str(" 1 2 3 4 5 6 7\n");
+    return result;
+}
+
+// Renders a position. Places in `highlight` are highlighted.
+pub fn render(board: Board, style: Style, highlight: BitBoard) -> String {
+    let (first, second) = if board.side_to_move() == board::FIRST_PLAYER {
+        (board.current(), board.other())
+    } else {
+        (board.other(), board.current())
+    };
+    return grid(style, highlight, |col, row| place(first, second, col, row));
+}
+
+// Renders the set bits of a bitboard like stones of the first player. Bits
+// outside of the 7x6 places (see BitBoard::out_of_bounds()) are listed below it.
+pub fn render_bitboard(bits: BitBoard, style: Style, highlight: BitBoard) -> String {
+    let mut result = grid(style, highlight, |col, row| {
+        place(bits, bitboard::empty(), col, row)
+    });
+    if !bits.in_bounds() {
+        result.push_str(&format!(
+            "Out of bounds: {:#x}\n",
+            bits.out_of_bounds().raw()
+        ));
+    }
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let board = board::from_moves("4453").unwrap();
+        let highlight = board.moves().for_column(2);
+        assert_eq!(
+            render(board, Style::Ascii, highlight),
+            " . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . . . . .\n . .(.)O . . .\n . . O X X . .\n 1 2 3 4 5 6 7\n"
+        );
+        assert_eq!(
+            render(board, Style::Unicode, bitboard::empty()),
+            " . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . ○ . . .\n . . ○ ● ● . .\n 1 2 3 4 5 6 7\n"
+        );
+        let ansi = render(board, Style::Ansi, highlight);
+        assert!(ansi.starts_with(" . . . . . . .\n"));
+        assert!(ansi.contains("\n . . \x1b[7m.\x1b[0m \x1b[33m●\x1b[0m . . .\n"));
+        assert!(ansi.ends_with(
+            " . . \x1b[33m●\x1b[0m \x1b[31m●\x1b[0m \x1b[31m●\x1b[0m . .\n 1 2 3 4 5 6 7\n"
+        ));
+    }
+
+    #[test]
+    fn test_render_bitboard() {
+        let bits = bitboard::empty().flip(0, 0).flip(6, 5);
+        assert_eq!(
+            render_bitboard(bits, Style::Ascii, bitboard::empty()),
+            " . . . . . . X\n . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . . . . .\n X . . . . . .\n 1 2 3 4 5 6 7\n"
+        );
+        let separator = bitboard::random(1 << 6);
+        assert!(render_bitboard(separator, Style::Ascii, bitboard::empty())
+            .ends_with("Out of bounds: 0x40\n"));
+    }
+
+    #[test]
+    fn test_render_neighbours() {
+        let board = board::from_moves("4453").unwrap();
+        let bottom = bitboard::empty().flip(0, 0).flip(1, 0).flip(6, 0);
+        let highlight = bottom.flip(2, 1).flip(3, 1).flip(4, 1);
+        assert_eq!(
+            render(board, Style::Ascii, highlight),
+            " . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . . . . .\n . .(. O .). .\n(. .)O X X .(.)\n 1 2 3 4 5 6 7\n"
+        );
+    }
+
+    #[test]
+    fn test_debug() {
+        // The first player threatens to complete the bottom row on both sides.
+        // almost_wins() includes the places that are already taken.
+        let board = board::from_moves("44556").unwrap();
+        let threats = board.other().almost_wins().intersect(board.moves());
+        assert_eq!(
+            format!("{:?}", threats),
+            "BitBoard(0x40000000040000)\n . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . . . . .\n . . X . . . X\n 1 2 3 4 5 6 7\n"
+        );
+        assert_eq!(
+            render(board, Style::Ascii, threats),
+            " . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . . . . .\n . . . O O . .\n . .(.)X X X(.)\n 1 2 3 4 5 6 7\n"
+        );
+        let header = format!(
+            "Board {{ current: {:#x}, other: {:#x} }}\n",
+            board.current().raw(),
+            board.other().raw()
+        );
+        assert_eq!(format!("{:?}", board), header + &board.to_string());
+    }
+}
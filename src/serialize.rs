@@ -0,0 +1,269 @@
+// Serialize and Deserialize for the types that can't derive them, with the
+// "serde" feature. Human-readable formats like JSON get columns from 1 to 7,
+// like the move strings and the solve service (see server.rs), others get
+// columns from 0 to 6 and numbers:
+// - Board: the moves of a game that leads to it (like "4453"), or encode().
+//   Positions that can't be reached in a game can't be serialized.
+// - Move lists (see `columns`): a sequence of columns.
+// - MoveStack: a struct with the `start` board and the `moves` list.
+// The solver results (Outcome, Analysis and Stats) derive them.
+use serde::de;
+use serde::ser;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use board;
+use board::{Board, MoveStack};
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return match self.move_sequence() {
+                Some(columns) => serializer.serialize_str(&board::format_moves(&columns)),
+                None => Err(ser::Error::custom("unreachable position")),
+            };
+        }
+        return serializer.serialize_u64(self.encode());
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+        if deserializer.is_human_readable() {
+            let moves = String::deserialize(deserializer)?;
+            return board::from_moves(&moves).ok_or_else(|| de::Error::custom("invalid moves"));
+        }
+        let encoded = u64::deserialize(deserializer)?;
+        return match board::decode(encoded) {
+            Some(board) if board.validate().is_ok() => Ok(board),
+            _ => Err(de::Error::custom("invalid position")),
+        };
+    }
+}
+
+// For fields with move lists: #[serde(with = "serialize::columns")].
+pub mod columns {
+    use serde::de;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    // Returns the number of the first column: 1 for human-readable formats.
+    fn first_column(human_readable: bool) -> u64 {
+        return if human_readable { 1 } else { 0 };
+    }
+
+    pub fn serialize<S: Serializer>(columns: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+        let first = first_column(serializer.is_human_readable());
+        let numbers: Vec<u64> = columns.iter().map(|col| col + first).collect();
+        return numbers.serialize(serializer);
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+        let first = first_column(deserializer.is_human_readable());
+        let numbers = Vec::<u64>::deserialize(deserializer)?;
+        if numbers
+            .iter()
+            .any(|number| !(first..first + 7).contains(number))
+        {
+            return Err(de::Error::custom("invalid column"));
+        }
+        return Ok(numbers.iter().map(|number| number - first).collect());
+    }
+}
+
+struct Columns<'a>(&'a [u64]);
+
+impl<'a> Serialize for Columns<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return columns::serialize(self.0, serializer);
+    }
+}
+
+impl Serialize for MoveStack {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("MoveStack", 2)?;
+        state.serialize_field("start", &self.start())?;
+        state.serialize_field("moves", &Columns(self.columns()))?;
+        return state.end();
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "MoveStack")]
+struct MoveList {
+    start: Board,
+    #[serde(with = "columns")]
+    moves: Vec<u64>,
+}
+
+impl<'de> Deserialize<'de> for MoveStack {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MoveStack, D::Error> {
+        let list = MoveList::deserialize(deserializer)?;
+        let mut stack = board::move_stack(list.start);
+        for col in list.moves {
+            stack
+                .play(col)
+                .map_err(|error| de::Error::custom(format!("invalid move: {:?}", error)))?;
+        }
+        return Ok(stack);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+
+    use serde_test::{
+        assert_de_tokens_error, assert_ser_tokens_error, assert_tokens, Compact, Configure,
+        Readable, Token,
+    };
+
+    use super::*;
+    use solver::{Analysis, Outcome, Stats};
+
+    // Move stacks don't implement PartialEq and Debug, which the assertions need.
+    #[derive(Serialize, Deserialize)]
+    #[serde(transparent)]
+    struct Checked<T>(T);
+
+    impl PartialEq for Checked<MoveStack> {
+        fn eq(&self, other: &Checked<MoveStack>) -> bool {
+            return self.0.start() == other.0.start() && self.0.columns() == other.0.columns();
+        }
+    }
+
+    impl fmt::Debug for Checked<MoveStack> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            return write!(f, "{:?}{:?}", self.0.start(), self.0.columns());
+        }
+    }
+
+    #[test]
+    fn test_board() {
+        let board = board::from_moves("4453").unwrap();
+        assert_tokens(&board.readable(), &[Token::Str("4453")]);
+        assert_tokens(&board.compact(), &[Token::U64(board.encode())]);
+        assert_de_tokens_error::<Readable<Board>>(&[Token::Str("44x")], "invalid moves");
+        assert_de_tokens_error::<Compact<Board>>(&[Token::U64(0)], "invalid position");
+
+        // Swapping a stone that isn't there gives both players a stone.
+        let overlap = board::empty().swap_stone(0, 0);
+        assert_ser_tokens_error(&overlap.readable(), &[], "unreachable position");
+    }
+
+    #[test]
+    fn test_move_stack() {
+        let stack = || {
+            let mut stack = board::move_stack(board::from_moves("44").unwrap());
+            stack.play(4).unwrap();
+            stack.play(2).unwrap();
+            return Checked(stack);
+        };
+        let tokens = |start: Token, moves: &[Token]| {
+            let mut tokens = vec![
+                Token::Struct {
+                    name: "MoveStack",
+                    len: 2,
+                },
+                Token::Str("start"),
+                start,
+                Token::Str("moves"),
+            ];
+            tokens.extend_from_slice(moves);
+            tokens.push(Token::StructEnd);
+            return tokens;
+        };
+        let readable_moves = [
+            Token::Seq { len: Some(2) },
+            Token::U64(5),
+            Token::U64(3),
+            Token::SeqEnd,
+        ];
+        assert_tokens(
+            &stack().readable(),
+            &tokens(Token::Str("44"), &readable_moves),
+        );
+        let start = Token::U64(board::from_moves("44").unwrap().encode());
+        let moves = [
+            Token::Seq { len: Some(2) },
+            Token::U64(4),
+            Token::U64(2),
+            Token::SeqEnd,
+        ];
+        assert_tokens(&stack().compact(), &tokens(start, &moves));
+        assert_de_tokens_error::<Readable<Checked<MoveStack>>>(
+            &tokens(
+                Token::Str("444444"),
+                &[Token::Seq { len: Some(1) }, Token::U64(4), Token::SeqEnd],
+            ),
+            "invalid move: ColumnFull",
+        );
+        assert_de_tokens_error::<Readable<Checked<MoveStack>>>(
+            &tokens(
+                Token::Str("44"),
+                &[Token::Seq { len: Some(1) }, Token::U64(0), Token::SeqEnd],
+            ),
+            "invalid column",
+        );
+    }
+
+    #[test]
+    fn test_analysis() {
+        let analysis = Analysis {
+            outcome: Outcome::Win,
+            score: 18,
+            best_moves: vec![2, 6],
+            scores: [
+                Some(17),
+                Some(17),
+                Some(18),
+                Some(17),
+                Some(17),
+                Some(17),
+                Some(18),
+            ],
+            stats: Stats {
+                nodes: 10,
+                time_ms: 1,
+            },
+        };
+        let mut tokens = vec![
+            Token::Struct {
+                name: "Analysis",
+                len: 5,
+            },
+            Token::Str("outcome"),
+            Token::UnitVariant {
+                name: "Outcome",
+                variant: "win",
+            },
+            Token::Str("score"),
+            Token::I64(18),
+            Token::Str("best_moves"),
+            Token::Seq { len: Some(2) },
+            Token::U64(3),
+            Token::U64(7),
+            Token::SeqEnd,
+            Token::Str("scores"),
+            Token::Tuple { len: 7 },
+        ];
+        for score in analysis.scores {
+            tokens.push(Token::Some);
+            tokens.push(Token::I64(score.unwrap()));
+        }
+        tokens.extend_from_slice(&[
+            Token::TupleEnd,
+            Token::Str("stats"),
+            Token::Struct {
+                name: "Stats",
+                len: 2,
+            },
+            Token::Str("nodes"),
+            Token::U64(10),
+            Token::Str("time_ms"),
+            Token::U64(1),
+            Token::StructEnd,
+            Token::StructEnd,
+        ]);
+        assert_tokens(&analysis.readable(), &tokens);
+    }
+}
@@ -0,0 +1,179 @@
+// A small HTTP service for solving positions. It understands a single request:
+//
+//   GET /solve?moves=4453
+//
+// where `moves` are columns from 1 to 7 (empty or missing for the empty board).
+// The response is JSON like this:
+//
+//   {"moves":"4453","outcome":"loss","score":-2,"best_moves":[3,6,7],
+//    "scores":[-5,-5,-2,-3,-4,-2,-2],"stats":{"nodes":97433070,"time_ms":19585}}
+//
+// `outcome` ("win", "draw" or "loss") and `score` are for the player to move,
+// see Solver::score(). `scores` has the score of every column after playing
+// it, or null if the column is full. `best_moves` are the columns with the
+// highest score. Invalid requests get a 4xx status and {"error":"<message>"}.
+// Apart from `moves`, this is how the "serde" feature serializes
+// solver::Analysis to JSON.
+//
+// Every connection is handled on its own thread. They share one solver, so its
+// caches stay warm across requests, but searches are done one at a time.
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use board;
+use solver::{Outcome, Solver};
+
+struct Response {
+    status: &'static str,
+    body: String,
+}
+
+fn error(status: &'static str, message: &str) -> Response {
+    return Response {
+        status,
+        body: format!("{{\"error\":\"{}\"}}", message),
+    };
+}
+
+// Returns the value of a query parameter. Values aren't percent-decoded, which
+// isn't needed for moves.
+fn query_parameter<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    for parameter in query.split('&') {
+        if let Some((key, value)) = parameter.split_once('=') {
+            if key == name {
+                return Some(value);
+            }
+        }
+    }
+    return None;
+}
+
+fn solve(solver: &Mutex<Solver>, moves: &str) -> Response {
+    let board = match board::from_moves(moves) {
+        Some(board) => board,
+        None => return error("400 Bad Request", "Invalid moves"),
+    };
+    if board.other().won() || board.moves().empty() {
+        return error("400 Bad Request", "Game is over");
+    }
+
+    let analysis = solver.lock().unwrap().analyze(board);
+    let outcome = match analysis.outcome {
+        Outcome::Win => "win",
+        Outcome::Draw => "draw",
+        Outcome::Loss => "loss",
+    };
+    let best_moves: Vec<String> = analysis
+        .best_moves
+        .iter()
+        .map(|col| (col + 1).to_string())
+        .collect();
+    let score_values: Vec<String> = analysis
+        .scores
+        .iter()
+        .map(|score| score.map_or("null".to_string(), |score| score.to_string()))
+        .collect();
+    return Response {
+        status: "200 OK",
+        body: format!(
+            "{{\"moves\":\"{}\",\"outcome\":\"{}\",\"score\":{},\"best_moves\":[{}],\"scores\":[{}],\"stats\":{{\"nodes\":{},\"time_ms\":{}}}}}",
+            moves,
+            outcome,
+            analysis.score,
+            best_moves.join(","),
+            score_values.join(","),
+            analysis.stats.nodes,
+            analysis.stats.time_ms
+        ),
+    };
+}
+
+// Returns the response for a request line like "GET /solve?moves=4453 HTTP/1.1".
+fn respond(solver: &Mutex<Solver>, request_line: &str) -> Response {
+    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    if parts.len() != 3 || !parts[2].starts_with("HTTP/") {
+        return error("400 Bad Request", "Invalid request");
+    }
+    let (path, query) = parts[1].split_once('?').unwrap_or((parts[1], ""));
+    if path != "/solve" {
+        return error("404 Not Found", "Not found");
+    }
+    if parts[0] != "GET" {
+        return error("405 Method Not Allowed", "Method not allowed");
+    }
+    return solve(solver, query_parameter(query, "moves").unwrap_or(""));
+}
+
+fn handle(solver: &Mutex<Solver>, stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, the request has no body.
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+    let response = respond(solver, request_line.trim_end());
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.body.len(),
+        response.body
+    )?;
+    return stream.flush();
+}
+
+// Serves requests until the listener fails.
+pub fn run(listener: TcpListener, solver: Solver) -> io::Result<()> {
+    let solver = Arc::new(Mutex::new(solver));
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let solver = solver.clone();
+        thread::spawn(move || {
+            // The client went away, there's no one to report to.
+            let _ = handle(&solver, stream);
+        });
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cache;
+    use solver;
+
+    #[test]
+    fn test_respond() {
+        let solver = Mutex::new(solver::new(16, cache::Format::Full));
+        let response = respond(&solver, "GET /solve?moves=445566 HTTP/1.1");
+        assert_eq!(response.status, "200 OK");
+        assert!(response.body.starts_with(
+            "{\"moves\":\"445566\",\"outcome\":\"win\",\"score\":18,\"best_moves\":[3,7],\"scores\":[17,17,18,17,17,17,18],\"stats\":{\"nodes\":"
+        ));
+        assert_eq!(
+            respond(&solver, "GET /solve?moves=4x HTTP/1.1").body,
+            "{\"error\":\"Invalid moves\"}"
+        );
+        assert_eq!(
+            respond(&solver, "GET /solve?moves=4455667 HTTP/1.1").body,
+            "{\"error\":\"Game is over\"}"
+        );
+        assert_eq!(
+            respond(&solver, "GET /other HTTP/1.1").status,
+            "404 Not Found"
+        );
+        assert_eq!(
+            respond(&solver, "POST /solve HTTP/1.1").status,
+            "405 Method Not Allowed"
+        );
+        assert_eq!(respond(&solver, "GET").status, "400 Bad Request");
+    }
+}
@@ -0,0 +1,849 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use bitboard::BitBoard;
+use board;
+use board::Board;
+use cache;
+use cache::{Cache, CacheBoard};
+use parity;
+#[cfg(feature = "serde")]
+use serialize;
+
+pub const DEBUG_PRINT_DEPTH: u64 = 5;
+const CACHE_DEPTH_SKIP: u64 = 2;
+const MOVE_ORDERING_MAX_DEPTH: u64 = 20;
+// Searches with a limit are only used to compute scores, after the outcome is
+// known. A smaller cache keeps clearing it cheap.
+const LIMIT_CACHE_LOG_SIZE: u64 = 20;
+// The stop flag is checked every this many examined moves.
+const STOP_CHECK_INTERVAL: u64 = 1 << 12;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MoveOrdering {
+    // Prefer moves that are part of the most possible four-in-a-rows.
+    WinsInvolving,
+    // Prefer moves that create the most threats, then like WinsInvolving.
+    Threats,
+}
+
+pub struct MinimaxState<'cache> {
+    pub moves_examined: u64,
+    pub cache: &'cache mut Cache,
+    pub first_player_can_draw: bool,
+    // If true, the cache entries of all children are prefetched before they
+    // are searched, so that the lookups don't stall on memory.
+    pub prefetch: bool,
+    // If true, enhanced transposition cutoffs are done: before searching any
+    // child, the cache is probed for all children. If one of them is known to
+    // be unsuccessful for the opponent, there is no need to search further.
+    pub etc: bool,
+    // If true, positions that are decided by the parity of the rows are not
+    // searched, see parity::analyse().
+    pub parity: bool,
+    pub ordering: MoveOrdering,
+    // If true, among moves with the same ordering score, moves that caused
+    // cutoffs before are preferred, weighted by the remaining depth. See `history`.
+    pub history_heuristic: bool,
+    // If true, among moves with the same ordering score, the move that last
+    // caused a cutoff at the same depth is tried first. See `killers`.
+    pub killer_heuristic: bool,
+    // Indexed by player (depth parity) and the bit of the move.
+    history: [[u32; 64]; 2],
+    // Indexed by depth.
+    killers: [Option<usize>; 43],
+    // Positions after moves at this depth are printed, to show progress.
+    pub debug_print_depth: Option<u64>,
+    // If set, only four-in-a-rows completed with at most this many stones on
+    // the board count, and later ones are considered a draw. This is used to
+    // determine how fast a position can be won.
+    pub limit: Option<u64>,
+    // If set, the search is aborted once the flag becomes true. The results of
+    // an aborted search are meaningless, and nothing is stored in the cache
+    // after the abort.
+    pub stop: Option<Arc<AtomicBool>>,
+    pub stopped: bool,
+}
+
+impl<'cache> MinimaxState<'cache> {
+    pub fn new(cache: &'cache mut Cache, first_player_can_draw: bool) -> MinimaxState<'cache> {
+        return MinimaxState {
+            moves_examined: 0,
+            cache,
+            first_player_can_draw,
+            prefetch: true,
+            etc: true,
+            parity: true,
+            ordering: MoveOrdering::Threats,
+            history_heuristic: true,
+            // Measured to be slightly worse when combined with the above.
+            killer_heuristic: false,
+            history: [[0; 64]; 2],
+            killers: [None; 43],
+            debug_print_depth: None,
+            limit: None,
+            stop: None,
+            stopped: false,
+        };
+    }
+
+    // For simplicity, this function returns true if the current player can
+    // force a "succesful" outcome: win or maybe a draw, see below.
+    // If `first_player_can_draw` is true, then drawing is considered a
+    // succesful outcome for the first player to move. Correspondingly,
+    // it is not a succesful outcome for the second player to move.
+    // And vice versa.
+    pub fn minimax(&mut self, board: Board) -> bool {
+        let depth = board.ply();
+        let cache_board = self.cache_board(board, depth);
+        return self.search(board, depth, cache_board);
+    }
+
+    // Returns whether a draw is successful for the player to move.
+    fn draw_result(&self, depth: u64) -> bool {
+        return self.first_player_can_draw == depth.is_multiple_of(2);
+    }
+
+    // Returns the cache board if positions at this depth are cached.
+    fn cache_board(&self, board: Board, depth: u64) -> Option<CacheBoard> {
+        if !depth.is_multiple_of(CACHE_DEPTH_SKIP) {
+            return None;
+        }
+        return Some(self.cache.board(board));
+    }
+
+    // Returns the order in which the columns are searched. Ties are broken by
+    // the distance to the center.
+    fn col_order(&self, board: Board, moves: BitBoard, depth: u64) -> [usize; 7] {
+        let mut col_order = [3, 2, 4, 1, 5, 6, 0];
+        if depth >= MOVE_ORDERING_MAX_DEPTH {
+            return col_order;
+        }
+        let mut scores: [u64; 7] = [0; 7];
+        for col in 0..scores.len() {
+            let move_ = moves.for_column(col as u64);
+            if move_.empty() {
+                scores[col] = 0;
+            } else {
+                scores[col] = self.score(board, move_, col, depth);
+            }
+            col_order[col] = col;
+            for i in (0..col).rev() {
+                let s1 = scores[col_order[i + 1]];
+                let s0 = scores[col_order[i]];
+                if s1 > s0 || (s1 == s0 && col_order[i + 1].abs_diff(3) < col_order[i].abs_diff(3))
+                {
+                    col_order.swap(i + 1, i);
+                }
+            }
+        }
+        return col_order;
+    }
+
+    // Returns the move ordering score of a (non-empty) move, higher is better.
+    fn score(&self, board: Board, move_: BitBoard, col: usize, depth: u64) -> u64 {
+        let wins_involving = board.wins_involving(move_) as u64;
+        let mut score = match self.ordering {
+            MoveOrdering::WinsInvolving => wins_involving,
+            // There are at most 16 possible four-in-a-rows involving a move.
+            MoveOrdering::Threats => ((board.threats_after(move_) as u64) << 5) | wins_involving,
+        } << 32;
+        if self.killer_heuristic && self.killers[depth as usize] == Some(col) {
+            score |= 1 << 31;
+        }
+        if self.history_heuristic {
+            let history = self.history[(depth % 2) as usize][move_.raw().trailing_zeros() as usize];
+            score |= std::cmp::min(history, (1 << 31) - 1) as u64;
+        }
+        return score;
+    }
+
+    fn record_cutoff(&mut self, move_: BitBoard, col: usize, depth: u64) {
+        if self.history_heuristic {
+            let remaining = 42 - depth as u32;
+            let entry =
+                &mut self.history[(depth % 2) as usize][move_.raw().trailing_zeros() as usize];
+            *entry = entry.saturating_add(remaining * remaining);
+        }
+        if self.killer_heuristic {
+            self.killers[depth as usize] = Some(col);
+        }
+    }
+
+    // Like minimax(), but with the result of cache_board() already computed.
+    fn search(&mut self, board: Board, depth: u64, cache_board: Option<CacheBoard>) -> bool {
+        if self.stopped {
+            return false;
+        }
+        if let Some(key) = cache_board {
+            if let Some(result) = self.cache.lookup(key) {
+                return result;
+            }
+        }
+
+        let moves = board.moves();
+        if moves.empty() {
+            return self.first_player_can_draw;
+        }
+        // The player to move never has a winning move here (the opponent would
+        // have blocked it), so the earliest win is the opponent's, after 2 moves.
+        let limit = self.limit.unwrap_or(42);
+        if depth + 2 > limit {
+            return self.draw_result(depth);
+        }
+        let moves = board.non_losing_moves(moves);
+        if moves.empty() {
+            return false;
+        }
+        // Now the opponent can't win after 2 moves, but we might after 3.
+        if depth + 3 > limit {
+            return self.draw_result(depth);
+        }
+        if self.parity {
+            // A verdict implies that the first player is to move.
+            match parity::analyse(board) {
+                // It's unknown how fast the second player wins.
+                Some(parity::Verdict::SecondPlayerWins) if self.limit.is_none() => return false,
+                Some(_) if !self.first_player_can_draw => return false,
+                _ => {}
+            }
+        }
+
+        self.moves_examined += 1;
+        if self.moves_examined.is_multiple_of(STOP_CHECK_INTERVAL) {
+            if let Some(stop) = &self.stop {
+                self.stopped = stop.load(Ordering::Relaxed);
+            }
+        }
+
+        // Compute the cache boards of the children (and issue the prefetches)
+        // first, so that the memory accesses overlap with the move ordering below.
+        let child_cache_boards_known = self.prefetch || self.etc;
+        let mut child_cache_boards = [None; 7];
+        if child_cache_boards_known {
+            for (col, child_cache_board) in child_cache_boards.iter_mut().enumerate() {
+                let move_ = moves.for_column(col as u64);
+                if move_.empty() {
+                    continue;
+                }
+                *child_cache_board = self.cache_board(board.do_move(move_), depth + 1);
+                if let Some(key) = *child_cache_board {
+                    if self.prefetch {
+                        self.cache.prefetch(key);
+                    }
+                }
+            }
+        }
+
+        let col_order = self.col_order(board, moves, depth);
+
+        if self.etc {
+            for child in child_cache_boards.iter().flatten() {
+                if self.cache.lookup(*child) == Some(false) {
+                    if let Some(key) = cache_board {
+                        self.cache.store(key, true);
+                    }
+                    return true;
+                }
+            }
+        }
+
+        let mut success = false;
+        for col in col_order {
+            let move_ = moves.for_column(col as u64);
+            if move_.empty() {
+                continue;
+            }
+            let moved_board = board.do_move(move_);
+            let moved_cache_board = if child_cache_boards_known {
+                child_cache_boards[col]
+            } else {
+                self.cache_board(moved_board, depth + 1)
+            };
+            let moved_result = self.search(moved_board, depth + 1, moved_cache_board);
+            if self.debug_print_depth == Some(depth) {
+                print!("{}", moved_board);
+                println!(
+                    "player={}, result={}, examined={}",
+                    moved_board.side_to_move(),
+                    moved_result,
+                    self.moves_examined
+                );
+            }
+            if !moved_result {
+                success = true;
+                self.record_cutoff(move_, col, depth);
+                break;
+            }
+        }
+
+        if self.stopped {
+            return false;
+        }
+        if let Some(key) = cache_board {
+            self.cache.store(key, success);
+        }
+        return success;
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    // Returns the outcome for the opponent.
+    pub fn flip(self) -> Outcome {
+        return match self {
+            Outcome::Loss => Outcome::Win,
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Win => Outcome::Loss,
+        };
+    }
+}
+
+// The result of Solver::analyze(). Columns are from 0 to 6.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Analysis {
+    // For the player to move.
+    pub outcome: Outcome,
+    pub score: i64,
+    // The columns with the highest score.
+    #[cfg_attr(feature = "serde", serde(with = "serialize::columns"))]
+    pub best_moves: Vec<u64>,
+    // The score after playing each column, or None if the column is full.
+    pub scores: [Option<i64>; 7],
+    pub stats: Stats,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Stats {
+    // The number of moves examined.
+    pub nodes: u64,
+    pub time_ms: u64,
+}
+
+// Solver determines outcomes (win, draw or loss) of positions with two
+// searches. It keeps a cache for both kinds of searches, so that the caches
+// stay warm across positions of the same game.
+pub struct Solver {
+    // For searches where a draw is a successful outcome for the first player.
+    draw_cache: Cache,
+    // For searches where a draw is not a successful outcome for the first player.
+    win_cache: Cache,
+    // For searches with a limit, cleared before every search.
+    limit_cache: Cache,
+    pub moves_examined: u64,
+    // Set to abort the current and later searches, see stop_flag().
+    stop: Arc<AtomicBool>,
+}
+
+// Creates a solver with two caches of 2^log_size entries each.
+pub fn new(log_size: u64, format: cache::Format) -> Solver {
+    return Solver {
+        draw_cache: cache::new(log_size, format),
+        win_cache: cache::new(log_size, format),
+        limit_cache: cache::new(std::cmp::min(log_size, LIMIT_CACHE_LOG_SIZE), format),
+        moves_examined: 0,
+        stop: Arc::new(AtomicBool::new(false)),
+    };
+}
+
+// Creates a solver whose caches take up about `megabytes` MB in total (rounded
+// down to a power of two).
+pub fn with_megabytes(megabytes: u64, format: cache::Format) -> Solver {
+    // The memory is split between the 2 caches.
+    let log_bytes = 63 - (megabytes << 20).leading_zeros() as u64 - 1;
+    return new(cache::log_size_for_bytes(log_bytes, format), format);
+}
+
+impl Solver {
+    // Returns the flag that aborts searches when set, which can be done from
+    // another thread. Results computed while the flag is set are meaningless,
+    // so callers should check stopped() afterwards. The flag stays set until
+    // it is cleared again.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        return self.stop.clone();
+    }
+
+    pub fn stopped(&self) -> bool {
+        return self.stop.load(Ordering::Relaxed);
+    }
+
+    // Turns the with_color_less() reduction of the cache keys on or off, see
+    // Cache::set_color_less(). Clears the caches.
+    pub fn set_color_less(&mut self, color_less: bool) {
+        for cache in [
+            &mut self.draw_cache,
+            &mut self.win_cache,
+            &mut self.limit_cache,
+        ] {
+            cache.set_color_less(color_less);
+            cache.clear();
+        }
+    }
+
+    // Removes all cache entries.
+    pub fn clear(&mut self) {
+        self.draw_cache.clear();
+        self.win_cache.clear();
+    }
+
+    fn search(&mut self, board: Board, first_player_can_draw: bool) -> bool {
+        let cache = if first_player_can_draw {
+            &mut self.draw_cache
+        } else {
+            &mut self.win_cache
+        };
+        let mut state = MinimaxState::new(cache, first_player_can_draw);
+        state.stop = Some(self.stop.clone());
+        let result = state.minimax(board);
+        self.moves_examined += state.moves_examined;
+        return result;
+    }
+
+    fn search_with_limit(&mut self, board: Board, first_player_can_draw: bool, limit: u64) -> bool {
+        self.limit_cache.clear();
+        let mut state = MinimaxState::new(&mut self.limit_cache, first_player_can_draw);
+        state.limit = Some(limit);
+        state.stop = Some(self.stop.clone());
+        let result = state.minimax(board);
+        self.moves_examined += state.moves_examined;
+        return result;
+    }
+
+    // Returns the outcome for the player to move. The position must not be
+    // won already.
+    pub fn solve(&mut self, board: Board) -> Outcome {
+        let moves = board.moves();
+        if board.can_win(moves) {
+            return Outcome::Win;
+        }
+        // The first player wins if a draw is unsuccessful for them, and the
+        // second player wins if a draw is unsuccessful for the first player.
+        let first_player_to_move = board.side_to_move() == board::FIRST_PLAYER;
+        if self.search(board, !first_player_to_move) {
+            return Outcome::Win;
+        }
+        if self.search(board, first_player_to_move) {
+            return Outcome::Draw;
+        }
+        return Outcome::Loss;
+    }
+
+    // Returns the score for the player to move. The score is like outcome, but also tells how fast
+    // the game is won, using the convention from http://blog.gamesolver.org/:
+    // 0 for a draw, and if the player to move wins with the winning stone
+    // being the n-th stone on the board, (44 - n) / 2. That is 1 when winning
+    // with the last stone, 2 with the second to last, etc. Losses are negative
+    // in the same way. The position must not be won already.
+    pub fn score(&mut self, board: Board) -> i64 {
+        let depth = board.ply();
+        if board.can_win(board.moves()) {
+            return score_for_win(depth + 1);
+        }
+        let first_player_to_move = board.side_to_move() == board::FIRST_PLAYER;
+        match self.solve(board) {
+            Outcome::Draw => return 0,
+            Outcome::Win => {
+                // Find the least number of stones for which we win. It's odd
+                // compared to `depth` and more than `depth + 1`.
+                let mut low = depth + 3;
+                let mut high = depth + 1 + 2 * ((42 - depth - 1) / 2);
+                while low < high {
+                    let mid = low + ((high - low) / 4) * 2;
+                    if self.search_with_limit(board, !first_player_to_move, mid) {
+                        high = mid;
+                    } else {
+                        low = mid + 2;
+                    }
+                }
+                return score_for_win(low);
+            }
+            Outcome::Loss => {
+                // Find the least number of stones for which the opponent wins.
+                let mut low = depth + 2;
+                let mut high = depth + 2 * ((42 - depth) / 2);
+                while low < high {
+                    let mid = low + ((high - low) / 4) * 2;
+                    if self.search_with_limit(board, first_player_to_move, mid) {
+                        low = mid + 2;
+                    } else {
+                        high = mid;
+                    }
+                }
+                return -score_for_win(low);
+            }
+        }
+    }
+
+    // Like solve_columns(), but returns scores.
+    pub fn score_columns(&mut self, board: Board) -> [Option<i64>; 7] {
+        let mut scores = [None; 7];
+        for (col, score) in scores.iter_mut().enumerate() {
+            *score = self.score_column(board, col as u64);
+        }
+        return scores;
+    }
+
+    // Returns the score for the player to move after playing the column, or
+    // None if the column is full.
+    pub fn score_column(&mut self, board: Board, col: u64) -> Option<i64> {
+        let move_ = board.moves().for_column(col);
+        if move_.empty() {
+            return None;
+        }
+        let moved_board = board.do_move(move_);
+        if moved_board.other().won() {
+            return Some(score_for_win(moved_board.ply()));
+        }
+        if moved_board.is_full() {
+            return Some(0);
+        }
+        return Some(-self.score(moved_board));
+    }
+
+    // Scores every column of a position. The game must not be over.
+    pub fn analyze(&mut self, board: Board) -> Analysis {
+        let start = Instant::now();
+        let moves_examined = self.moves_examined;
+        let scores = self.score_columns(board);
+        let score = scores.iter().flatten().max().copied().unwrap();
+        return Analysis {
+            outcome: outcome_for_score(score),
+            score,
+            best_moves: (0..7)
+                .filter(|col| scores[*col as usize] == Some(score))
+                .collect(),
+            scores,
+            stats: Stats {
+                nodes: self.moves_examined - moves_examined,
+                time_ms: start.elapsed().as_millis() as u64,
+            },
+        };
+    }
+
+    // Returns the moves (columns) that follow when both players play the best
+    // move: the fastest win, or the slowest loss. The line ends when the game
+    // is over.
+    pub fn principal_variation(&mut self, mut board: Board) -> Vec<u64> {
+        let mut line = Vec::new();
+        while !board.other().won() && !board.is_full() {
+            let col = best_column(self.score_columns(board)).unwrap();
+            board = board.do_move(board.moves().for_column(col));
+            line.push(col);
+        }
+        return line;
+    }
+
+    // Returns the outcome for the player to move after playing each column,
+    // or None if the column is full.
+    pub fn solve_columns(&mut self, board: Board) -> [Option<Outcome>; 7] {
+        let mut outcomes = [None; 7];
+        let moves = board.moves();
+        for (col, outcome) in outcomes.iter_mut().enumerate() {
+            let move_ = moves.for_column(col as u64);
+            if move_.empty() {
+                continue;
+            }
+            let moved_board = board.do_move(move_);
+            *outcome = Some(if moved_board.other().won() {
+                Outcome::Win
+            } else if moved_board.is_full() {
+                Outcome::Draw
+            } else {
+                self.solve(moved_board).flip()
+            });
+        }
+        return outcomes;
+    }
+}
+
+// Returns the score for a player that wins by placing the `stones`-th stone.
+fn score_for_win(stones: u64) -> i64 {
+    return ((44 - stones) / 2) as i64;
+}
+
+pub fn outcome_for_score(score: i64) -> Outcome {
+    return match score {
+        0 => Outcome::Draw,
+        s if s > 0 => Outcome::Win,
+        _ => Outcome::Loss,
+    };
+}
+
+// Given the result of Solver::solve_columns() or Solver::score_columns(),
+// returns a column with the best outcome, preferring columns closer to the
+// center. Returns None if the board is full.
+pub fn best_column<T: Ord + Copy>(outcomes: [Option<T>; 7]) -> Option<u64> {
+    let mut best: Option<usize> = None;
+    for col in [3, 2, 4, 1, 5, 0, 6] {
+        let better = match best {
+            None => outcomes[col].is_some(),
+            Some(best) => outcomes[col] > outcomes[best],
+        };
+        if better {
+            best = Some(col);
+        }
+    }
+    return best.map(|col| col as u64);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::read_to_string;
+    use std::path::Path;
+
+    use super::*;
+    use board;
+    use verify;
+
+    // Reads positions as (move count, board, score), using the format as described here:
+    // http://blog.gamesolver.org/solving-connect-four/02-test-protocol/
+    fn read_positions(fname: &str) -> Vec<(u64, Board, i64)> {
+        let path = Path::new("testdata").join(fname);
+        let contents = read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{}: {}, see testdata/README.md", path.display(), e));
+        let mut positions = Vec::new();
+        for line in contents.lines() {
+            let line_parts: Vec<_> = line.split(" ").collect();
+            assert_eq!(line_parts.len(), 2);
+            let moves: &str = line_parts[0];
+            let score: i64 = line_parts[1].parse().unwrap();
+            let mut board = board::empty();
+            for mov in moves.as_bytes() {
+                let possible_moves = board.moves();
+                let move_ = possible_moves.for_column((mov - b'1') as u64);
+                assert!(!move_.empty());
+                board = board.do_move(move_);
+            }
+            positions.push((moves.len() as u64, board, score));
+        }
+        return positions;
+    }
+
+    // Returns the log size of the caches for tests that solve many positions,
+    // which can be overridden with the TEST_CACHE_LOG_SIZE environment variable.
+    fn test_cache_log_size(default: u64) -> u64 {
+        return match std::env::var("TEST_CACHE_LOG_SIZE") {
+            Ok(value) => value.parse().expect("Invalid TEST_CACHE_LOG_SIZE"),
+            Err(_) => default,
+        };
+    }
+
+    // Checks the outcomes of the first `count` positions of a file.
+    fn test_positions_from_file(fname: &str, count: usize, cache_log_size: u64) {
+        let mut solver = new(test_cache_log_size(cache_log_size), cache::Format::Full);
+        for (depth, board, score) in read_positions(fname).into_iter().take(count) {
+            println!("{} {}", depth, score);
+            match solver.solve(board) {
+                Outcome::Win => assert!(score > 0),
+                Outcome::Draw => assert!(score == 0),
+                Outcome::Loss => assert!(score < 0),
+            }
+        }
+    }
+
+    // Returns `count` positions after `depth` random moves, none of which
+    // completed a four-in-a-row.
+    fn random_positions(count: usize, depth: u64, mut seed: u64) -> Vec<Board> {
+        return (0..count)
+            .map(|_| verify::random_position(depth, &mut seed).1)
+            .collect();
+    }
+
+    #[test]
+    fn test_parity_verdicts() {
+        let mut checked = 0;
+        for depth in [24, 28, 32, 36] {
+            for board in random_positions(1000, depth, depth) {
+                let verdict = match parity::analyse(board) {
+                    Some(verdict) => verdict,
+                    None => continue,
+                };
+                let mut win_cache = cache::new(16, cache::Format::Full);
+                let mut win_setup = MinimaxState::new(&mut win_cache, false);
+                win_setup.parity = false;
+                assert!(!win_setup.minimax(board));
+                let mut draw_cache = cache::new(16, cache::Format::Full);
+                let mut draw_setup = MinimaxState::new(&mut draw_cache, true);
+                draw_setup.parity = false;
+                if verdict == parity::Verdict::SecondPlayerWins {
+                    assert!(!draw_setup.minimax(board));
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    // Computes the score without any tricks.
+    fn naive_score(board: Board, depth: u64) -> i64 {
+        let moves = board.moves();
+        let mut best = None;
+        for col in 0..7 {
+            let move_ = moves.for_column(col);
+            if move_.empty() {
+                continue;
+            }
+            let moved_board = board.do_move(move_);
+            let score = if moved_board.other().won() {
+                score_for_win(depth + 1)
+            } else {
+                -naive_score(moved_board, depth + 1)
+            };
+            best = std::cmp::max(best, Some(score));
+        }
+        return best.unwrap_or(0);
+    }
+
+    #[test]
+    fn test_scores() {
+        for depth in [30, 31, 32, 33] {
+            for board in random_positions(100, depth, depth) {
+                let mut solver = new(16, cache::Format::Full);
+                assert_eq!(solver.score(board), naive_score(board, depth));
+            }
+        }
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut solver = new(16, cache::Format::Full);
+        solver.stop_flag().store(true, Ordering::Relaxed);
+        // Would take very long with such a small cache.
+        solver.solve(board::empty());
+        assert!(solver.stopped());
+
+        solver.stop_flag().store(false, Ordering::Relaxed);
+        for board in random_positions(20, 30, 30) {
+            assert_eq!(solver.score(board), naive_score(board, 30));
+        }
+    }
+
+    // The fast default tier. Curated_R1 is committed, so this runs without
+    // downloading anything.
+    #[test]
+    fn test_curated_positions() {
+        let mut solver = new(test_cache_log_size(20), cache::Format::Full);
+        for (_, board, score) in read_positions("Curated_R1") {
+            assert_eq!(solver.score(board), score);
+        }
+    }
+
+    // The first positions of the files with the fewest moves left to solve.
+    const FAST_POSITION_COUNT: usize = 100;
+
+    // Needs the downloaded files (see testdata/README.md), so it only runs when
+    // asked for, like test_positions_full.
+    #[test]
+    #[ignore]
+    fn test_positions() {
+        test_positions_from_file("Test_L3_R1", FAST_POSITION_COUNT, 22);
+        test_positions_from_file("Test_L2_R1", FAST_POSITION_COUNT, 22);
+        test_positions_from_file("Test_L2_R2", FAST_POSITION_COUNT, 22);
+    }
+
+    // Takes about an hour, run with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_positions_full() {
+        let all = usize::MAX;
+        test_positions_from_file("Test_L3_R1", all, 27); // 4GB cache.
+        test_positions_from_file("Test_L2_R1", all, 27);
+        test_positions_from_file("Test_L2_R2", all, 27);
+        test_positions_from_file("Test_L1_R1", all, 27);
+        test_positions_from_file("Test_L1_R2", all, 27);
+        test_positions_from_file("Test_L1_R3", all, 27);
+    }
+
+    // Benchmarks need nightly Rust.
+    #[cfg(feature = "nightly")]
+    mod benches {
+        extern crate test;
+
+        use super::*;
+
+        // Solves the first BENCH_POSITION_COUNT positions of Curated_R1 with a cleared
+        // cache, so every iteration does the same amount of work.
+        const BENCH_POSITION_COUNT: usize = 60;
+        // 256MB, well above the size of the CPU caches, like the default 1GB. The
+        // cache is allocated once, clearing it is part of every iteration.
+        const BENCH_CACHE_LOG_SIZE: u64 = 24;
+
+        fn bench_positions(b: &mut test::Bencher, configure: fn(&mut MinimaxState)) {
+            let positions = read_positions("Curated_R1");
+            let mut cache = cache::new(BENCH_CACHE_LOG_SIZE, cache::Format::Full);
+            b.iter(|| {
+                cache.clear();
+                let mut state = MinimaxState::new(&mut cache, false);
+                configure(&mut state);
+                for (_, board, _) in positions.iter().take(BENCH_POSITION_COUNT) {
+                    test::black_box(state.minimax(*board));
+                }
+            });
+        }
+
+        #[bench]
+        fn bench_positions_default(b: &mut test::Bencher) {
+            bench_positions(b, |_| {});
+        }
+
+        #[bench]
+        fn bench_positions_no_prefetch(b: &mut test::Bencher) {
+            bench_positions(b, |state| state.prefetch = false);
+        }
+
+        // Without ETC either, the keys of the children aren't computed up front.
+        #[bench]
+        fn bench_positions_no_prefetch_no_etc(b: &mut test::Bencher) {
+            bench_positions(b, |state| {
+                state.prefetch = false;
+                state.etc = false;
+            });
+        }
+
+        #[bench]
+        fn bench_positions_no_etc(b: &mut test::Bencher) {
+            bench_positions(b, |state| state.etc = false);
+        }
+
+        #[bench]
+        fn bench_positions_no_parity(b: &mut test::Bencher) {
+            bench_positions(b, |state| state.parity = false);
+        }
+
+        #[bench]
+        fn bench_positions_wins_involving_ordering(b: &mut test::Bencher) {
+            bench_positions(b, |state| {
+                state.ordering = MoveOrdering::WinsInvolving;
+                state.history_heuristic = false;
+                state.killer_heuristic = false;
+            });
+        }
+
+        #[bench]
+        fn bench_positions_no_history(b: &mut test::Bencher) {
+            bench_positions(b, |state| state.history_heuristic = false);
+        }
+
+        #[bench]
+        fn bench_positions_killers(b: &mut test::Bencher) {
+            bench_positions(b, |state| state.killer_heuristic = true);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// Verifies that the with_color_less() reduction of cache keys is sound: two
+// positions with the same cache key must have the same outcome.
+//
+// The reduced board itself can't be solved instead of the original: it gives
+// the dead stones (stones that can't be part of a four-in-a-row of their
+// owner) to both players, so a four-in-a-row through a dead stone would count
+// for the opponent as well. Instead, dead stones of a random position are given
+// to the other player one by one, as long as the cache key stays the same.
+// The resulting position is solved independently of the original, by solvers
+// that don't use the reduction (so they can't share cache entries). The
+// original is also solved with the reduction, which must agree as well.
+use std::io;
+use std::io::Write;
+
+use board;
+use board::Board;
+use cache;
+use solver;
+use solver::Solver;
+
+// Returns a random column from 0 to 6. `seed` must not be 0.
+fn random_column(seed: &mut u64) -> u64 {
+    // xorshift
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 7;
+    *seed ^= *seed << 17;
+    return *seed % 7;
+}
+
+// Returns the moves (columns from 1 to 7) and the position of a random game
+// after `moves` moves, none of which completed a four-in-a-row.
+pub fn random_position(moves: u64, seed: &mut u64) -> (String, Board) {
+    loop {
+        let mut board = board::empty();
+        let mut played = String::new();
+        let mut attempts = 0;
+        while (played.len() as u64) < moves && attempts < 100 {
+            attempts += 1;
+            let col = random_column(seed);
+            let move_ = board.moves().for_column(col);
+            if move_.empty() || board.do_move(move_).other().won() {
+                continue;
+            }
+            board = board.do_move(move_);
+            played.push((b'1' + col as u8) as char);
+        }
+        if played.len() as u64 == moves {
+            return (played, board);
+        }
+    }
+}
+
+// Returns the positions of a random game, from the empty board up to the
+// position where the last move completed a four-in-a-row or filled the board.
+pub fn random_game(seed: &mut u64) -> Vec<Board> {
+    let mut board = board::empty();
+    let mut positions = vec![board];
+    while board.outcome().is_none() {
+        if let Ok(moved_board) = board.play(random_column(seed)) {
+            board = moved_board;
+            positions.push(board);
+        }
+    }
+    return positions;
+}
+
+// Returns a different position with the same cache key, made by giving dead
+// stones to the other player, or None if no dead stone can be given away.
+pub fn same_key_position(board: Board) -> Option<Board> {
+    let key = board.with_color_less();
+    let dead = key.current().intersect(key.other());
+    let mut result = board;
+    for col in 0..7 {
+        for row in 0..6 {
+            if !dead.is_set(col, row) {
+                continue;
+            }
+            let swapped = result.swap_stone(col, row);
+            if swapped.with_color_less() == key
+                && !swapped.current().won()
+                && !swapped.other().won()
+            {
+                result = swapped;
+            }
+        }
+    }
+    if result == board {
+        return None;
+    }
+    return Some(result);
+}
+
+// Checks `count` random positions after `moves` moves as described above and
+// writes the mismatches to `output`. Every solver uses caches of `megabytes`
+// MB. Returns the number of mismatches.
+pub fn verify_color_less<W: Write>(
+    megabytes: u64,
+    count: u64,
+    moves: u64,
+    mut seed: u64,
+    output: &mut W,
+) -> io::Result<u64> {
+    let mut reduced_solver = solver::with_megabytes(megabytes, cache::Format::Full);
+    let mut original_solver = new_plain_solver(megabytes);
+    let mut swapped_solver = new_plain_solver(megabytes);
+    let mut swapped_count = 0;
+    let mut mismatches = 0;
+    for _ in 0..count {
+        let (played, board) = random_position(moves, &mut seed);
+        let original = original_solver.solve(board);
+        let reduced = reduced_solver.solve(board);
+        let swapped = same_key_position(board).map(|swapped| {
+            swapped_count += 1;
+            return swapped_solver.solve(swapped);
+        });
+        if reduced != original || swapped.is_some_and(|swapped| swapped != original) {
+            mismatches += 1;
+            writeln!(
+                output,
+                "Mismatch after {}: {:?} without reduction, {:?} with reduction, {:?} for the same key",
+                played, original, reduced, swapped
+            )?;
+        }
+    }
+    writeln!(
+        output,
+        "Checked {} positions ({} with a different position for the same key), {} mismatches",
+        count, swapped_count, mismatches
+    )?;
+    return Ok(mismatches);
+}
+
+fn new_plain_solver(megabytes: u64) -> Solver {
+    let mut solver = solver::with_megabytes(megabytes, cache::Format::Full);
+    solver.set_color_less(false);
+    return solver;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_key_position() {
+        let mut seed = 1;
+        let mut found = 0;
+        for _ in 0..100 {
+            let (_, board) = random_position(30, &mut seed);
+            if let Some(swapped) = same_key_position(board) {
+                assert!(swapped != board);
+                let mut cache = cache::new(16, cache::Format::Full);
+                cache.store(cache.board(board), true);
+                assert_eq!(cache.lookup(cache.board(swapped)), Some(true));
+                found += 1;
+            }
+        }
+        assert!(found > 0);
+    }
+
+    #[test]
+    fn test_verify_color_less() {
+        let mut output = Vec::new();
+        assert_eq!(verify_color_less(1, 50, 30, 1, &mut output).unwrap(), 0);
+    }
+}
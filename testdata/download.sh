@@ -0,0 +1,7 @@
+#!/bin/sh
+# Downloads the test positions of http://blog.gamesolver.org/solving-connect-four/02-test-protocol/
+# into this directory.
+cd "$(dirname "$0")" || exit 1
+for name in Test_L3_R1 Test_L2_R1 Test_L2_R2 Test_L1_R1 Test_L1_R2 Test_L1_R3; do
+    curl -O "http://blog.gamesolver.org/data/$name" || exit 1
+done
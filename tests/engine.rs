@@ -0,0 +1,147 @@
+// Drives the engine protocol (see src/engine.rs) through a pipe, like a GUI.
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+struct Engine {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+fn start() -> Engine {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_connect4-rust"))
+        .arg("engine")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let input = child.stdin.take().unwrap();
+    let output = BufReader::new(child.stdout.take().unwrap());
+    return Engine {
+        child,
+        input,
+        output,
+    };
+}
+
+impl Engine {
+    fn send(&mut self, line: &str) {
+        writeln!(self.input, "{}", line).unwrap();
+        self.input.flush().unwrap();
+    }
+
+    // Returns all lines up to and including the first that starts with `prefix`.
+    fn read_until(&mut self, prefix: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            assert!(
+                self.output.read_line(&mut line).unwrap() > 0,
+                "Unexpected end of output"
+            );
+            let line = line.trim_end().to_string();
+            let done = line.starts_with(prefix);
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+    }
+
+    fn quit(mut self) {
+        self.send("quit");
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+#[test]
+fn test_handshake() {
+    let mut engine = start();
+    engine.send("uci");
+    let lines = engine.read_until("uciok");
+    assert_eq!(lines[0], "id name connect4-rust");
+    assert!(lines.contains(&"option name Hash type spin default 1024 min 1 max 65536".to_string()));
+    engine.send("setoption name Hash value 16");
+    engine.send("setoption name CacheFormat value compact");
+    engine.send("isready");
+    assert_eq!(engine.read_until("readyok"), vec!["readyok"]);
+    engine.send("setoption name Hash value 0");
+    engine.send("foo");
+    engine.send("isready");
+    assert_eq!(
+        engine.read_until("readyok"),
+        vec![
+            "info string Invalid value for Hash",
+            "info string Unknown command foo",
+            "readyok"
+        ]
+    );
+    engine.quit();
+}
+
+#[test]
+fn test_go() {
+    let mut engine = start();
+    engine.send("setoption name Hash value 16");
+    engine.send("ucinewgame");
+    // The first player wins with 3 or 7, and 3 is closer to the center.
+    engine.send("position startpos moves 4 4 5 5 6 6");
+    engine.send("go");
+    let lines = engine.read_until("bestmove");
+    assert_eq!(lines.len(), 9);
+    assert!(lines.contains(&"info currmove 3 score 18".to_string()));
+    assert!(lines.contains(&"info currmove 7 score 18".to_string()));
+    assert!(lines[7].starts_with("info score 18 nodes "));
+    assert_eq!(lines[8], "bestmove 3");
+
+    // Without a new position, the same position is solved again.
+    engine.send("go");
+    let lines = engine.read_until("bestmove");
+    assert_eq!(lines[lines.len() - 1], "bestmove 3");
+
+    // The game is over.
+    engine.send("position startpos moves 4455667");
+    engine.send("go");
+    assert_eq!(engine.read_until("bestmove"), vec!["bestmove none"]);
+
+    engine.send("position startpos moves 4x");
+    engine.send("isready");
+    assert_eq!(
+        engine.read_until("readyok"),
+        vec!["info string Invalid moves", "readyok"]
+    );
+    engine.quit();
+}
+
+#[test]
+fn test_stop() {
+    let mut engine = start();
+    engine.send("setoption name Hash value 16");
+    // Solving the empty board takes very long with a small cache.
+    engine.send("position startpos");
+    engine.send("go");
+    engine.send("isready");
+    assert_eq!(engine.read_until("readyok"), vec!["readyok"]);
+    engine.send("stop");
+    assert_eq!(engine.read_until("bestmove"), vec!["bestmove 4"]);
+    engine.quit();
+}
+
+#[test]
+fn test_new_game_while_searching() {
+    let mut engine = start();
+    engine.send("setoption name Hash value 16");
+    engine.send("position startpos moves 4453");
+    engine.send("go");
+    // The search may have reported some columns already.
+    engine.send("isready");
+    engine.read_until("readyok");
+    // Stops the search, which still reports its best column.
+    engine.send("ucinewgame");
+    engine.send("isready");
+    let lines = engine.read_until("readyok");
+    assert!(lines[lines.len() - 2].starts_with("bestmove "));
+    engine.send("stop");
+    engine.quit();
+}
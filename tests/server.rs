@@ -0,0 +1,105 @@
+// Starts the solve service (see src/server.rs) and sends it requests, like a
+// client would.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+
+struct Server {
+    child: Child,
+    address: String,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn start() -> Server {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_connect4-rust"))
+        .args(["--cache-size=16", "serve", "127.0.0.1:0"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(child.stdout.as_mut().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    let address = line
+        .trim_end()
+        .strip_prefix("Listening on http://")
+        .unwrap()
+        .to_string();
+    return Server { child, address };
+}
+
+// Returns the status line and the body.
+fn get(address: &str, path: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, address).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.lines().next().unwrap().to_string();
+    assert!(head.contains(&format!("Content-Length: {}", body.len())));
+    return (status, body.to_string());
+}
+
+// Removes the stats, which differ between runs.
+fn without_stats(body: &str) -> &str {
+    return &body[..body.find(",\"stats\":").unwrap()];
+}
+
+#[test]
+fn test_solve() {
+    let server = start();
+    let (status, body) = get(&server.address, "/solve?moves=445566");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert_eq!(
+        without_stats(&body),
+        "{\"moves\":\"445566\",\"outcome\":\"win\",\"score\":18,\"best_moves\":[3,7],\"scores\":[17,17,18,17,17,17,18]"
+    );
+    assert!(body.ends_with("}}"));
+
+    assert_eq!(
+        get(&server.address, "/solve?moves=12345678"),
+        (
+            "HTTP/1.1 400 Bad Request".to_string(),
+            "{\"error\":\"Invalid moves\"}".to_string()
+        )
+    );
+    assert_eq!(get(&server.address, "/").0, "HTTP/1.1 404 Not Found");
+}
+
+#[test]
+fn test_concurrent_requests() {
+    let server = start();
+    let game = "62352673232646611533624214375411";
+    let expected = [
+        (28, "\"outcome\":\"loss\",\"score\":-7,\"best_moves\":[1,4,5,7],\"scores\":[-7,null,null,-7,-7,null,-7]"),
+        (29, "\"outcome\":\"win\",\"score\":7,\"best_moves\":[5],\"scores\":[-5,null,null,-6,7,null,-5]"),
+        (30, "\"outcome\":\"win\",\"score\":6,\"best_moves\":[4],\"scores\":[-6,null,null,6,-5,null,-6]"),
+        (31, "\"outcome\":\"win\",\"score\":6,\"best_moves\":[5],\"scores\":[-5,null,null,-5,6,null,-5]"),
+    ];
+    let mut clients = Vec::new();
+    for _ in 0..4 {
+        for (ply, result) in expected {
+            let address = server.address.clone();
+            clients.push(thread::spawn(move || {
+                let moves = &game[..ply];
+                let (status, body) = get(&address, &format!("/solve?moves={}", moves));
+                assert_eq!(status, "HTTP/1.1 200 OK");
+                assert_eq!(
+                    without_stats(&body),
+                    format!("{{\"moves\":\"{}\",{}", moves, result)
+                );
+            }));
+        }
+    }
+    for client in clients {
+        client.join().unwrap();
+    }
+}